@@ -0,0 +1,83 @@
+// by wuwbobo2021 <https://github.com/wuwbobo2021>, <wuwbobo@outlook.com>
+// If you have found bugs in this program, please pull an issue, or contact me.
+
+#ifndef RAMDOM_PICKER_DISTRIBUTION_H
+#define RAMDOM_PICKER_DISTRIBUTION_H
+
+#include "table.h"
+#include "weighted_index.h"
+
+#include <random>
+#include <stdexcept>
+#include <string>
+#include <vector>
+
+namespace RandomPicker
+{
+
+// a snapshot of probabilities of possible items in a table, picked by a WeightedIndex. like std::discrete_distribution,
+// `dist(engine)` gives the index of an item, `dist.name(i)` gives its name. later modifications of the table don't affect it.
+class Distribution
+{
+	std::vector<std::string> m_names;
+	WeightedIndex m_index; //constructed after m_names
+	
+	static std::vector<double> probabilities_of(const Table& table, std::vector<std::string>& names);
+	
+public:
+	Distribution(const Table& table); //the table should be valid with at least one possible item
+	
+	unsigned int count() const;
+	const std::string& name(unsigned int index) const;
+	double probability(unsigned int index) const;
+	
+	template <class URBG> unsigned int operator()(URBG& engine) const;
+	template <class URBG> const std::string& sample(URBG& engine) const;
+};
+
+inline std::vector<double> Distribution::probabilities_of(const Table& table, std::vector<std::string>& names)
+{
+	Table prob; table.probabilities(prob);
+	if (prob.is_empty())
+		throw std::invalid_argument("Distribution::Distribution(): no possible item.");
+	
+	std::vector<double> vals;
+	for (unsigned int i = 0; i < prob.count(); i++) {
+		names.push_back(prob[i].name()); vals.push_back(prob[i].value());
+	}
+	return vals;
+}
+
+inline Distribution::Distribution(const Table& table):
+	m_index(probabilities_of(table, m_names))
+{}
+
+inline unsigned int Distribution::count() const
+{
+	return m_names.size();
+}
+
+inline const std::string& Distribution::name(unsigned int index) const
+{
+	return m_names[index];
+}
+
+inline double Distribution::probability(unsigned int index) const
+{
+	return m_index.weight(index) / m_index.total();
+}
+
+template <class URBG>
+unsigned int Distribution::operator()(URBG& engine) const
+{
+	return m_index(engine);
+}
+
+template <class URBG>
+const std::string& Distribution::sample(URBG& engine) const
+{
+	return m_names[(*this)(engine)];
+}
+
+}
+#endif
@@ -1,9 +1,13 @@
 // by wuwbobo2021 <https://github.com/wuwbobo2021>, <wuwbobo@outlook.com>
 // If you have found bugs in this program, please pull an issue, or contact me.
 
-#include "picker.h"
+#include "random_picker.h"
 
+#include <cstdlib>
+#include <fstream>
+#include <iomanip>
 #include <iostream>
+#include <limits>
 #include <sstream>
 
 using namespace std;
@@ -11,50 +15,141 @@ using namespace std;
 RandomPicker::Table table;
 RandomPicker::Picker picker(table);
 
+int run(int argc, char** argv); //main() without catching exceptions
 void config(string& save_path);
+bool check_table();
+void print_input_errors(const vector<RandomPicker::InputError>& errors);
+void print_bars(const RandomPicker::Table& tbl);
+void print_series(const vector<RandomPicker::Table>& series, unsigned int first);
+void save_result(const RandomPicker::Table& result, const string& path);
 void print_help();
 unsigned int read_value(const string& str);
+bool read_range(const string& str, unsigned int& first, unsigned int& last);
 bool ask_yes_no();
 
 int main(int argc, char** argv)
 {
-	bool arg_help = false, arg_show_table = false, arg_config = false, arg_test = false;
-	unsigned int arg_amount = 0; string arg_path = "";
-	bool flag_opened = false;
+	// an exception means a bug or a case missed by the checks in run(), it shouldn't abort the program.
+	try {
+		return run(argc, argv);
+	} catch (const std::invalid_argument& e) {
+		cerr << "Error: " << e.what() << '\n';
+		return 1;
+	}
+}
+
+int run(int argc, char** argv)
+{
+	bool arg_help = false, arg_show_table = false, arg_config = false, arg_test = false, arg_json = false;
+	unsigned int arg_amount = 0, arg_amount_min = 0; string arg_path = ""; //arg_amount_min is set for a range
+	bool arg_seeded = false, arg_bars = false; unsigned int arg_seed = 0;
+	unsigned int arg_repeat = 1; string arg_output = "";
+	bool flag_opened = false; vector<RandomPicker::InputError> errors;
 	
 	string arg;
 	for (unsigned int i = 1; i < argc; i++) {
 		arg = argv[i];
-		if (arg[0] == '-' && arg.length() > 1) {
+		if (arg == "--seed") {
+			if (i + 1 >= argc) {print_help(); return 0;}
+			arg_seeded = true; arg_seed = read_value(argv[++i]);
+		} else if (arg == "-") {
+			arg_path = arg; table.clear();
+			if (table.input(cin, errors)) flag_opened = true;
+		} else if (arg[0] == '-' && arg.length() > 1) {
 			switch (arg[1]) {
 				case 'h': arg_help = true; break;
 				case 's': arg_show_table = true; break;
 				case 'c': arg_config = true; break;
 				case 't': arg_test = true; break;
+				case 'j': arg_json = true; break;
+				case 'b': arg_bars = true; break;
+				case 'o':
+					if (i + 1 >= argc) {print_help(); return 0;}
+					arg_output = argv[++i]; break;
+				case 'r':
+					if (i + 1 < argc) arg_repeat = read_value(argv[++i]);
+					if (arg_repeat == 0) {print_help(); return 0;}
+					break;
 				default: break;
 			}
 		} else {
-			unsigned int val = read_value(arg);
-			if (val > 0 && (val <= table.count() || table.repetitive_picking))
+			// numbers are amounts, which are checked by validate_amount() after the table is opened.
+			unsigned int val = read_value(arg), first, last;
+			if (read_range(arg, first, last)) {
+				arg_amount_min = first; arg_amount = last;
+			} else if (val > 0 && arg == to_string(val))
 				arg_amount = val;
 			else {
 				arg_path = arg;
-				if (table.open(arg_path)) flag_opened = true; 
+				if (table.open(arg_path, errors)) flag_opened = true;
 			}
 		}
 	}
 	
 	if (arg_help || arg_path.length() == 0) {print_help(); return 0;}
 	
+	if (! errors.empty()) {
+		print_input_errors(errors);
+		if (! arg_config) {
+			cout << "Please fix the lines above in \"" << arg_path << "\".\n";
+			return 0;
+		}
+	}
+	
+	if (arg_config && arg_path == "-") {print_help(); return 0;}
 	if (arg_config) {config(arg_path); return 0;}
 	else if (!flag_opened || arg_amount == 0) {print_help(); return 0;}
+	if (arg_amount_min > 0 && (!arg_show_table || arg_output.length() > 0)) {print_help(); return 0;}
+	
+	string reason;
+	if (! table.validate_amount(arg_amount, reason)) {
+		// check_table() prints the error if the table itself is invalid
+		if (check_table()) cout << "Invalid amount " << arg_amount << ": " << reason << ".\n";
+		return 0;
+	}
+	
+	if (arg_show_table && arg_amount_min > 0) {
+		if (! arg_json) table.output(cout);
+		if (! check_table()) return 0;
+		vector<RandomPicker::Table> series; picker.calculate_series(arg_amount, series);
+		if (arg_json) {
+			// an object keyed by amounts, like {"2": {...}, "3": {...}}
+			cout << '{';
+			for (unsigned int k = arg_amount_min; k <= arg_amount; k++) {
+				ostringstream sst; series[k - 1].output_json(sst);
+				string str = sst.str(); str.pop_back(); //'\n'
+				cout << (k > arg_amount_min? ", \"" : "\"") << k << "\": " << str;
+			}
+			cout << "}\n";
+		} else {
+			cout << "\nAbsolute values (%) for amounts " << arg_amount_min << " to " << arg_amount << ":\n";
+			print_series(series, arg_amount_min);
+		}
+		return 0;
+	}
 	
-	if (arg_show_table) {
+	if (arg_show_table && arg_json) {
+		if (! check_table()) return 0;
+		RandomPicker::Table cal; picker.calculate(arg_amount, cal);
+		save_result(cal, arg_output);
+		cal.output_json(cout);
+		return 0;
+	} else if (arg_show_table) {
 		table.output(cout);
+		if (! check_table()) return 0;
 		if (table.count() > 0 && arg_amount > 0) {
-			RandomPicker::Table cal; picker.calculate(arg_amount, cal);
+			RandomPicker::Table cal; bool approx = false;
+			double complexity = picker.calculate_complexity(arg_amount);
+			if (complexity > 1e8) {
+				cout << "\nThe calculation will visit about " << complexity << " groups of items "
+				     << "and may take a long time. Estimate by 1,000,000 groups of results instead?";
+				approx = ask_yes_no();
+			}
+			if (approx) picker.calculate_approx(arg_amount, 1000000, cal);
+			else picker.calculate(arg_amount, cal);
+			save_result(cal, arg_output);
 			cal.scale(100.0);
-			cout << "\nAbsolute values (%):\n";
+			cout << (approx? "\nEstimated absolute values (%):\n" : "\nAbsolute values (%):\n");
 			cal.output(cout);
 			if (table.repetitive_picking)
 				cout << "Note: Probabilities in this table are for a picking operation of a single item, "
@@ -63,28 +158,55 @@ int main(int argc, char** argv)
 		return 0;
 	}
 	
+	if (arg_seeded) picker.seed(arg_seed);
+	
 	if (! arg_test) {
 		vector<string> result;
-		picker.pick(arg_amount, result);
-		for (unsigned int i = 0; i < result.size(); i++)
-			cout << result[i] << ' ';
-		cout << '\n';
+		for (unsigned int r = 0; r < arg_repeat; r++) {
+			result.clear();
+			picker.pick(arg_amount, result);
+			if (arg_json) {
+				cout << '[';
+				for (unsigned int i = 0; i < result.size(); i++)
+					cout << (i > 0? ", \"" : "\"") << result[i] << '"';
+				cout << "]\n";
+			} else {
+				for (unsigned int i = 0; i < result.size(); i++)
+					cout << result[i] << ' ';
+				cout << '\n';
+			}
+		}
+	} else if (arg_json) {
+		RandomPicker::Table result;
+		picker.test(1000000, arg_amount, result);
+		result.repetitive_picking = table.repetitive_picking;
+		result.scale(table.repetitive_picking? 1.0 / (1000000.0*arg_amount) : 1.0 / 1000000.0);
+		save_result(result, arg_output);
+		result.output_json(cout);
 	} else {
-		std::random_device ran_dev;
-		cout << "entropy() returned by current standard library random_device: "
-		     << ran_dev.entropy() << ".\n";
+		if (arg_seeded)
+			cout << "Using std::mt19937 with seed " << arg_seed << ".\n";
+		else {
+			std::random_device ran_dev;
+			cout << "entropy() returned by current standard library random_device: "
+			     << ran_dev.entropy() << ".\n";
+		}
 		
 		RandomPicker::Table result;
 		picker.test(1000000, arg_amount, result);
+		result.repetitive_picking = table.repetitive_picking;
 		
 		if (! table.repetitive_picking) {
-			result.scale(1.0 / 10000.0);
+			result.scale(1.0 / 1000000.0); save_result(result, arg_output);
+			result.scale(100.0);
 			cout << "Test result indicating probabilities (%) of occurence in a group of results:\n";
 		} else {
-			result.scale(1.0 / (10000.0*arg_amount));
+			result.scale(1.0 / (1000000.0*arg_amount)); save_result(result, arg_output);
+			result.scale(100.0);
 			cout << "Test result of frequencies (%):\n";
 		}
-		result.output(cout);
+		if (arg_bars) print_bars(result);
+		else result.output(cout);
 	}
 	
 	return 0;
@@ -104,16 +226,21 @@ void config(string& save_path)
 	     << "the lower the probability of being picked up.\n";
 	table.power_inversed = ask_yes_no();
 	
-	cout << "Input items, seperate names and power values with spaces, "
+	cout << "Input items, seperate names and power values with spaces or `=`, "
+	     << "change existing values with `+=`, `-=`, `*=` or `/=`, "
 	     << "delete item with `delete <name>`, input end at last:\n";
-	if (! table.input(cin))
+	vector<RandomPicker::InputError> errors;
+	if (! table.input(cin, errors)) {
+		print_input_errors(errors);
 		cout << "Sorry, part of your input is not recorded. Make sure your names consist of "
 		     << "letters, digits, or underline characters, without any space.\n";
+	}
 	
 	cout << "Please check the recorded items below:\n";
 	table.output(cout);
 	
 	if (table.is_empty()) return;
+	check_table();
 	
 	while (! table.save(save_path)) {
 		cout << "Sorry, failed to save file \"" << save_path << "\".\n"
@@ -122,6 +249,77 @@ void config(string& save_path)
 	}
 }
 
+bool check_table()
+{
+	string name, reason;
+	if (table.check(name, reason)) return true;
+	
+	if (name.length() > 0)
+		cout << "Invalid item \"" << name << "\": " << reason << ".\n";
+	else
+		cout << "Invalid table: " << reason << ".\n";
+	return false;
+}
+
+void print_input_errors(const vector<RandomPicker::InputError>& errors)
+{
+	for (unsigned int i = 0; i < errors.size(); i++)
+		cout << "line " << errors[i].line << ": \"" << errors[i].text << "\" ignored.\n";
+}
+
+void print_bars(const RandomPicker::Table& tbl)
+{
+	unsigned int columns = 80;
+	const char* env = getenv("COLUMNS");
+	if (env && read_value(env) > 0) columns = read_value(env);
+	
+	unsigned int name_width = 0; double max = 0;
+	for (unsigned int i = 0; i < tbl.count(); i++) {
+		if (tbl[i].name().length() > name_width) name_width = tbl[i].name().length();
+		if (tbl[i].value() > max) max = tbl[i].value();
+	}
+	
+	// name | ###### | 12.34%
+	unsigned int bar_width = (columns > name_width + 16)? columns - name_width - 16 : 10;
+	for (unsigned int i = 0; i < tbl.count(); i++) {
+		unsigned int len = (max > 0)? (unsigned int)(tbl[i].value() / max * bar_width + 0.5) : 0;
+		cout << left << setw(name_width) << tbl[i].name() << " | "
+		     << string(len, '#') << string(bar_width - len, ' ') << " | "
+		     << right << fixed << setprecision(2) << setw(6) << tbl[i].value() << "%\n";
+	}
+	cout.unsetf(ios_base::floatfield); cout << setprecision(6);
+}
+
+void print_series(const vector<RandomPicker::Table>& series, unsigned int first)
+{
+	// items as rows and amounts as columns
+	cout << "amount\t";
+	for (unsigned int k = first; k <= series.size(); k++)
+		cout << '\t' << k;
+	cout << '\n';
+	
+	const RandomPicker::Table& last = series.back();
+	for (unsigned int i = 0; i < last.count(); i++) {
+		cout << last[i].name() << '\t';
+		for (unsigned int k = first; k <= series.size(); k++)
+			cout << '\t' << series[k - 1].item_value(last[i].name()) * 100.0;
+		cout << '\n';
+	}
+}
+
+void save_result(const RandomPicker::Table& result, const string& path)
+{
+	if (path.length() == 0) return;
+	
+	// probabilities are written with all digits, so that they are read back without loss.
+	if (! result.is_empty()) {
+		ofstream ofs(path);
+		ofs << setprecision(numeric_limits<double>::max_digits10);
+		if (ofs.is_open() && result.output(ofs)) return;
+	}
+	cerr << "Sorry, failed to save file \"" << path << "\".\n";
+}
+
 void print_help()
 {
 	cout << "random-picker <table_file> <amount>\n"
@@ -130,7 +328,14 @@ void print_help()
 	     << "-c <file>\t\tDo configuration and save table file\n"
 	     << "-s <file> [amount]\tPrint current table, show table of absolute values if amount is given\n"
 	     << "-t <file> <amount>\tTest the random engine by statistics of 1,000,000 groups of results\n"
-		 << "Note: When repetitive mode is off, <amount> must not exceed amount of items in the table.\n";
+	     << "-o <file>\t\tSave probabilities calculated by -s or tested by -t as a table file\n"
+	     << "-r <count>\t\tPick <count> groups of items, one group per line\n"
+	     << "-b\t\t\tShow the result of -t as a bar chart\n"
+	     << "-j\t\t\tPrint results of -s, -t or picking in JSON\n"
+	     << "--seed <seed>\t\tUse a seeded pseudo-random engine for reproducible picking and testing\n"
+		 << "Note: When repetitive mode is off, <amount> must not exceed amount of items in the table.\n"
+		 << "The amount of -s can be a range like `3-5` or `3..5`, then a column (or a JSON key with -j) is shown for each amount.\n"
+		 << "The table is read from standard input if <file> is `-`, except for -c.\n";
 }
 
 unsigned int read_value(const string& str)
@@ -142,6 +347,19 @@ unsigned int read_value(const string& str)
 	sst >> val; return val;
 }
 
+// reads a range like `3-5` or `3..5`, the whole string must be the range (`1-5.txt` is not).
+bool read_range(const string& str, unsigned int& first, unsigned int& last)
+{
+	size_t pos = str.find(".."), len = 2;
+	if (pos == string::npos) {pos = str.find('-'); len = 1;}
+	if (pos == string::npos || pos == 0) return false;
+	
+	string str_first = str.substr(0, pos), str_last = str.substr(pos + len);
+	first = read_value(str_first); last = read_value(str_last);
+	if (str_first != to_string(first) || str_last != to_string(last)) return false;
+	return first > 0 && last >= first;
+}
+
 bool ask_yes_no()
 {
 	static char buf[4096];
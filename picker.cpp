@@ -2,122 +2,462 @@
 // If you have found bugs in this program, please pull an issue, or contact me.
 
 #include "picker.h"
+#include "weighted_index.h"
+#include <algorithm>
+#include <cassert>
+#include <chrono>
+#include <cmath>
 #include <iostream>
 using namespace RandomPicker;
 
+// defining RANDOM_PICKER_TRACE makes calculations print node counts and durations to std::clog.
+#ifdef RANDOM_PICKER_TRACE
+#define TRACE_BEGIN() auto trace_begin = std::chrono::steady_clock::now(); unsigned long long trace_nodes = 0
+#define TRACE_NODE() trace_nodes++
+#define TRACE_END(func) std::clog << "random-picker: " << func << ": " << trace_nodes << " nodes in " \
+	<< std::chrono::duration<double, std::milli>(std::chrono::steady_clock::now() - trace_begin).count() << " ms\n"
+#else
+#define TRACE_BEGIN() ((void)0)
+#define TRACE_NODE() ((void)0)
+#define TRACE_END(func) ((void)0)
+#endif
+
+const unsigned int Dot_Tree_Max_Nodes = 1000;
+
+static void check_amount(const char* func, unsigned int amount, unsigned int max)
+{
+	if (amount > max)
+		throw std::invalid_argument(std::string(func) + ": invalid amount " + std::to_string(amount)
+		                            + ", there are only " + std::to_string(max) + " items.");
+}
+
+bool Picker::contains(const std::string& name) const
+{
+	return m_table.item_value(name) > 0;
+}
+
+void Picker::items(std::vector<std::string>& result) const
+{
+	result.clear();
+	for (unsigned int i = 0; i < m_table.count(); i++)
+		if (m_table[i].value() > 0) result.push_back(m_table[i].name());
+}
+
+unsigned int Picker::pick_index()
+{
+	this->draw();
+	if (m_table.count() == 0 || m_grid[m_table.count()] == 0)
+		throw std::invalid_argument("Picker::pick_index(): no possible item.");
+	return this->draw_index();
+}
+
+const std::vector<unsigned int>& Picker::pick_indexes(unsigned int amount)
+{
+	this->pick(amount, m_indexes);
+	return m_indexes;
+}
+
 void Picker::pick(unsigned int amount, std::vector<unsigned int>& result)
 {
+	result.clear();
 	if (amount == 0 || m_table.count() == 0) return;
-	if (!m_table.repetitive_picking && amount > m_table.count())
-		throw std::invalid_argument("Picker::pick(): invalid amount.");
 	
 	if (! m_flag_testing) {
 		this->draw(); m_vect_picked.resize(m_table.count());
 	}
+	if (m_grid[m_table.count()] == 0) return;
+	if (! m_table.repetitive_picking) check_amount("Picker::pick()", amount, m_cnt_possible);
+	
+	result.resize(amount);
+	this->pick_to(amount, result.data());
+}
+
+void Picker::pick_groups(unsigned int groups, unsigned int amount, std::vector<unsigned int>& result)
+{
+	result.clear();
+	if (groups == 0 || amount == 0 || m_table.count() == 0) return;
+	
+	this->draw(); m_vect_picked.resize(m_table.count());
+	if (m_grid[m_table.count()] == 0) return;
+	if (! m_table.repetitive_picking) check_amount("Picker::pick_groups()", amount, m_cnt_possible);
+	
+	result.resize(groups * amount);
+	for (unsigned int i = 0; i < groups; i++)
+		this->pick_to(amount, result.data() + i * amount);
+}
+
+void Picker::pick_to(unsigned int amount, unsigned int* dest)
+{
 	char* picked = m_vect_picked.data(); //bool
-	double width = m_grid[m_table.count()]; if (width == 0) return;
-	double r; unsigned int n;
+	unsigned int n;
 	
 	for (unsigned int i = 0; i < m_table.count(); i++)
 		picked[i] = false;
 	
-	result.clear();
 	for (unsigned int i = 0; i < amount; i++) {
-		r = this->random_value(width);
-		n = this->determine(r);
+		n = this->draw_index();
 		if (! m_table.repetitive_picking) {
 			if (picked[n]) {i--; continue;}
 			picked[n] = true;
 		}
-		result.push_back(n);
+		dest[i] = n;
 	}
 }
 
 void Picker::pick(unsigned int amount, std::vector<std::string>& result)
 {
+	result.clear();
 	if (amount == 0 || m_table.count() == 0) return;
 	
 	std::vector<unsigned int> vect;
 	this->pick(amount, vect);
-	for (unsigned int i = 0; i < amount; i++)
+	for (unsigned int i = 0; i < vect.size(); i++)
 		result.push_back(m_table[vect[i]].name());
 }
 
-void Picker::test(unsigned int times, unsigned int amount, Table& result)
+void Picker::pick(unsigned int amount, std::vector<const Item*>& result)
 {
-	if (amount == 0 || times == 0) return;
+	result.clear();
+	if (amount == 0 || m_table.count() == 0) return;
 	
-	unsigned int stat[m_table.count()] = {0};
 	std::vector<unsigned int> vect;
-	this->draw(); if (m_grid[m_table.count()] == 0) return;
-	m_vect_picked.resize(m_table.count());
-	m_flag_testing = true;
-	for (unsigned int i = 0; i < times; i++) {
-		this->pick(amount, vect);
-		for (unsigned int i = 0; i < amount; i++)
-			stat[vect[i]]++;
+	this->pick(amount, vect);
+	for (unsigned int i = 0; i < vect.size(); i++)
+		result.push_back(&m_table[vect[i]]);
+}
+
+void Picker::pick_each(unsigned int amount, const std::function<void(unsigned int)>& callback)
+{
+	if (amount == 0 || m_table.count() == 0) return;
+	
+	this->draw(); m_vect_picked.resize(m_table.count());
+	if (m_grid[m_table.count()] == 0) return;
+	if (! m_table.repetitive_picking) check_amount("Picker::pick_each()", amount, m_cnt_possible);
+	
+	char* picked = m_vect_picked.data(); //bool
+	for (unsigned int i = 0; i < m_table.count(); i++)
+		picked[i] = false;
+	
+	for (unsigned int i = 0; i < amount; i++) {
+		unsigned int n = this->draw_index();
+		if (! m_table.repetitive_picking) {
+			if (picked[n]) {i--; continue;}
+			picked[n] = true;
+		}
+		callback(n);
 	}
+}
+
+void Picker::pick_chunks(unsigned int amount, const std::function<bool(const std::vector<unsigned int>&)>& callback)
+{
+	if (amount == 0 || m_table.count() == 0) return;
+	
+	this->draw(); m_vect_picked.resize(m_table.count());
+	if (m_grid[m_table.count()] == 0) return;
+	if (! m_table.repetitive_picking) check_amount("Picker::pick_chunks()", amount, m_cnt_possible);
 	
-	m_flag_testing = false;
+	std::vector<unsigned int> group(amount); //reused for each group
+	do {
+		this->pick_to(amount, group.data());
+	} while (callback(group));
+}
+
+bool Picker::pick_min_distinct(unsigned int amount, unsigned int min_distinct, unsigned int max_tries,
+                               std::vector<unsigned int>& result)
+{
 	result.clear();
+	
+	this->draw(); m_vect_picked.resize(m_table.count());
+	if (! m_table.repetitive_picking) check_amount("Picker::pick_min_distinct()", amount, m_cnt_possible);
+	if (min_distinct > amount || min_distinct > m_cnt_possible)
+		throw std::invalid_argument("Picker::pick_min_distinct(): the constraint can't be satisfied.");
+	if (amount == 0) return true;
+	if (m_cnt_possible == 0) return false;
+	
+	std::vector<char> vect_distinct(m_table.count()); //bool
+	result.resize(amount);
+	for (unsigned int t = 0; t < max_tries; t++) {
+		this->pick_to(amount, result.data());
+		
+		std::fill(vect_distinct.begin(), vect_distinct.end(), false);
+		unsigned int cnt_distinct = 0;
+		for (unsigned int i = 0; i < amount; i++) {
+			if (vect_distinct[result[i]]) continue;
+			vect_distinct[result[i]] = true; cnt_distinct++;
+		}
+		if (cnt_distinct >= min_distinct) return true;
+	}
+	
+	result.clear(); return false;
+}
+
+unsigned int Picker::reset()
+{
+	this->draw();
+	m_vect_picked.assign(m_table.count(), false);
+	m_group.clear(); m_group_picked.clear();
+	m_group_possible = 0; m_group_rest = 0; m_flag_group = false;
+	return m_cnt_possible;
+}
+
+void Picker::begin_group()
+{
+	this->draw();
+	m_group.clear();
+	m_group_picked.assign(m_table.count(), false);
+	m_group_possible = m_cnt_possible;
+	m_group_rest = m_grid.empty()? 0 : m_grid[m_table.count()];
+	m_flag_group = true;
+}
+
+unsigned int Picker::draw_next()
+{
+	if (! m_flag_group) this->begin_group();
+	// m_group_picked and the grid are of the items in the table when the group began.
+	if (m_group_picked.size() != m_table.count())
+		throw std::logic_error("Picker::draw_next(): the table is resized, reset() should be called.");
+	if (m_group.size() >= m_group_possible)
+		throw std::invalid_argument("Picker::draw_next(): no item remaining.");
+	
+	// the random value is drawn in the width of remaining cells, so that the time doesn't grow
+	// as the remaining probability shrinks (like rejecting picked items would do).
 	unsigned int cnt = m_table.count();
+	double val = this->random_value(m_group_rest), cur = 0;
+	unsigned int n = cnt;
 	for (unsigned int i = 0; i < cnt; i++) {
-		Item item(m_table[i].name(), stat[i]);
+		if (m_group_picked[i] || m_grid[i + 1] == m_grid[i]) continue;
+		n = i; cur += m_grid[i + 1] - m_grid[i];
+		if (val < cur) break; //else n is the last remaining item if val reaches m_group_rest
+	}
+	if (m_on_pick) m_on_pick(n);
+	
+	m_group_picked[n] = true; m_group.push_back(n);
+	m_group_rest -= m_grid[n + 1] - m_grid[n];
+	if (m_group.size() >= m_group_possible || m_group_rest < 0) m_group_rest = 0; //no rounding error is left
+	return n;
+}
+
+void Picker::finish_group(std::vector<unsigned int>& result)
+{
+	result = m_group;
+	m_group.clear(); m_flag_group = false;
+}
+
+void Picker::finish_group(std::vector<std::string>& result)
+{
+	std::vector<unsigned int> vect;
+	this->finish_group(vect);
+	this->replay(vect, result);
+}
+
+std::string Picker::sample_and_remove()
+{
+	return m_table[this->draw_next()].name();
+}
+
+std::string Picker::sample_and_remove(double& remaining)
+{
+	unsigned int n = this->draw_next();
+	remaining = m_group_rest / m_grid[m_table.count()];
+	
+	return m_table[n].name();
+}
+
+void Picker::replay(const std::vector<unsigned int>& indexes, std::vector<std::string>& result) const
+{
+	for (unsigned int i = 0; i < indexes.size(); i++)
+		if (indexes[i] >= m_table.count())
+			throw std::invalid_argument("Picker::replay(): invalid index.");
+	
+	result.clear();
+	for (unsigned int i = 0; i < indexes.size(); i++)
+		result.push_back(m_table[indexes[i]].name());
+}
+
+void Picker::cumulative(Table& result)
+{
+	result.clear();
+	this->draw(); if (m_grid.size() < 2) return;
+	
+	double width = m_grid[m_table.count()]; if (width == 0) return;
+	for (unsigned int i = 0; i < m_table.count(); i++) {
+		Item item(m_table[i].name(), m_grid[i + 1] / width);
 		result.item(item);
 	}
 }
 
-void Picker::draw()
+void Picker::unreachable_items(std::vector<std::string>& result)
 {
-	m_grid.clear();
-	if (m_table.is_empty()) return;
+	result.clear();
+	this->draw(); if (m_grid.size() < 2) return;
 	
-	Table tbl = m_table;
-	if (tbl.power_inversed) tbl.inverse();
+	// random values are (width * k / range), in which k is an integer from 0 to range.
+	double width = m_grid[m_table.count()];
+	double range = this->random_range();
+	for (unsigned int i = 0; i < m_table.count(); i++) {
+		if (m_table[i].value() == 0) continue;
+		double k = std::ceil(m_grid[i] * range / width);
+		bool reachable = false;
+		for (double j = k - 1; j <= k + 1 && !reachable; j++)
+			if (j >= 0 && j <= range && this->determine(m_grid, width * j / range) == i) reachable = true;
+		if (! reachable) result.push_back(m_table[i].name());
+	}
+}
+
+void Picker::shuffle(std::vector<unsigned int>& result)
+{
+	result.clear();
+	if (m_table.count() == 0) return;
+	this->draw();
 	
+	// the item of the largest key u^(1/w) is the first one (Efraimidis-Spirakis), log(u)/w is used instead.
+	std::vector<double> keys(m_table.count());
+	for (unsigned int i = 0; i < m_table.count(); i++) {
+		double w = m_grid[i + 1] - m_grid[i];
+		if (w <= 0) continue;
+		keys[i] = std::log(this->random_value(1.0)) / w;
+		result.push_back(i);
+	}
+	std::sort(result.begin(), result.end(), [&](unsigned int a, unsigned int b) {return keys[a] > keys[b];});
+}
+
+void Picker::shuffle(std::vector<std::string>& result)
+{
+	std::vector<unsigned int> vect;
+	this->shuffle(vect);
+	result.clear();
+	for (unsigned int i = 0; i < vect.size(); i++)
+		result.push_back(m_table[vect[i]].name());
+}
+
+void Picker::test(unsigned int times, unsigned int amount, Table& result)
+{
 	unsigned int cnt = m_table.count();
-	double cur = 0;
+	unsigned int stat[cnt] = {0};
+	std::vector<unsigned int> vect;
+	if (amount > 0 && times > 0) this->draw();
+	if (amount > 0 && times > 0 && m_grid[cnt] > 0) {
+		// the amount is checked before pick() skips drawing the grid, which must be restored on any exception.
+		if (! m_table.repetitive_picking) check_amount("Picker::test()", amount, m_cnt_possible);
+		m_vect_picked.resize(cnt);
+		m_flag_testing = true;
+		try {
+			for (unsigned int i = 0; i < times; i++) {
+				this->pick(amount, vect);
+				for (unsigned int i = 0; i < amount; i++)
+					stat[vect[i]]++;
+			}
+		} catch (...) {
+			m_flag_testing = false; throw;
+		}
+		m_flag_testing = false;
+	}
+	
+	result.clear();
 	for (unsigned int i = 0; i < cnt; i++) {
-		m_grid.push_back(cur);
-		cur += tbl[i].value();
+		if (!m_flag_keep_impossible && m_table[i].value() == 0) continue;
+		Item item(m_table[i].name(), stat[i]);
+		result.item(item);
 	}
-	m_grid.push_back(cur);
 }
 
-unsigned int Picker::determine(double val) const
+void Picker::make_grid(std::vector<double>& grid) const
+{
+	m_table.grid(grid);
+}
+
+void Picker::draw()
+{
+	this->make_grid(m_grid);
+	m_cnt_possible = Table::count_possible(m_grid);
+	
+	m_int_grid.clear();
+	if (m_flag_integer && m_table.is_integral()) {
+		for (unsigned int i = 0; i <= m_table.count(); i++)
+			m_int_grid.push_back((unsigned long long)m_grid[i]);
+	}
+}
+
+unsigned int Picker::draw_index()
+{
+	unsigned int n;
+	if (m_int_grid.empty())
+		n = this->determine(m_grid, this->random_value(m_grid[m_table.count()]));
+	else
+		n = this->determine_integer();
+	
+	if (m_on_pick) m_on_pick(n);
+	return n;
+}
+
+unsigned int Picker::index_of(unsigned long long raw) const
 {
-	unsigned int sz = m_grid.size();
+	std::vector<double> grid; this->make_grid(grid);
+	if (grid.empty() || grid.back() == 0)
+		throw std::invalid_argument("Picker::index_of(): no possible item.");
+	
+	double val = grid.back() * ((double)raw / 18446744073709551616.0); //2^64
+	return this->determine(grid, val);
+}
+
+unsigned int Picker::determine_integer()
+{
+	// a random integer in [0, total) is made by rejecting 64-bit random values not less than a
+	// multiple of total, so that each integer has the same probability.
+	unsigned long long total = m_int_grid.back(), r;
+	unsigned long long limit = (~0ull / total) * total;
+	do {
+		r = ((unsigned long long)this->random_bits() << 32) | this->random_bits();
+	} while (r >= limit);
+	r %= total;
+	
+	unsigned int i = std::upper_bound(m_int_grid.begin(), m_int_grid.end(), r) - m_int_grid.begin();
+	return i - 1;
+}
+
+unsigned int Picker::determine(const std::vector<double>& grid, double val) const
+{
+	unsigned int sz = grid.size();
 	if (sz <= 2) return 0;
 	
-	const double* p = m_grid.data();
-	if (val == p[sz - 1]) return sz - 2;
-	for (unsigned int i = 0; i < sz - 1; i++)
-		if (val >= p[i] && val < p[i + 1]) return i;
+	const double* p = grid.data();
+	if (m_boundary_epsilon > 0) {
+		for (unsigned int i = 0; i < sz - 1; i++)
+			if (p[i + 1] > p[i] && val < p[i + 1] + m_boundary_epsilon) return i;
+	}
 	
-	return 0; //it should be impossible
+	// it should be impossible unless the grid is corrupted, which must not bias any item silently.
+	if (! (val >= p[0] && val <= p[sz - 1])) {
+		assert(false);
+		throw std::runtime_error("Picker::determine(): no item matches the random value.");
+	}
+	return WeightedIndex::find(p + 1, sz - 1, val); //the grid begins with 0
 }
 
 void Picker::calculate(unsigned int pick_amount, Table& result) const
 {
-	if (pick_amount == 0 || m_table.count() == 0) return;
-	if (!m_table.repetitive_picking && pick_amount > m_table.count())
-		throw std::invalid_argument("Picker::calculate(): invalid amount.");
-		
-	Table tbl = m_table; tbl.remove_impossible();
-	if (tbl.power_inversed) tbl.inverse();
+	result.clear();
+	if (m_table.count() == 0) return;
+	
+	Table tbl; this->effective_table(tbl);
 	
 	double width = 0;
 	for (unsigned int i = 0; i < tbl.count(); i++)
 		width += tbl[i].value();
 	if (width == 0) return;
+	if (! m_table.repetitive_picking) check_amount("Picker::calculate()", pick_amount, tbl.count());
 	
-	if (tbl.repetitive_picking || pick_amount == 1) {
+	if (pick_amount == 0) {
+		for (unsigned int i = 0; i < tbl.count(); i++)
+			tbl[i].value(0);
+		result = tbl; this->add_impossible(result); return;
+	} else if (tbl.repetitive_picking || pick_amount == 1) {
 		tbl.scale(1.0 / width);
-		result = tbl; return;
+		result = tbl; this->add_impossible(result); return;
 	} else if (pick_amount == tbl.count()) {
 		for (unsigned int i = 0; i < tbl.count(); i++)
 			tbl[i].value(1);
-		result = tbl; return;
+		result = tbl; this->add_impossible(result); return;
 	}
 	
 	// the depth varies from 0 to pick_amount - 1, depth 0 is at the forest ground;
@@ -129,9 +469,11 @@ void Picker::calculate(unsigned int pick_amount, Table& result) const
 	unsigned int dep = 0; double cur_width = width; bool flag_back = false;
 	stack_pro[0] = 1.0;
 	
+	TRACE_BEGIN();
 	while (true) {
 		unsigned int i = stack[dep]; bool pre_picked = picked[i]; double cur_pro;
 		if (!pre_picked && !flag_back) {
+			TRACE_NODE();
 			picked[i] = true;
 			cur_pro = stack_pro[dep] * tbl[i].value() / cur_width;
 			pro[i] += cur_pro;
@@ -156,9 +498,422 @@ void Picker::calculate(unsigned int pick_amount, Table& result) const
 		}
 	}
 	
+	TRACE_END("Picker::calculate()");
+	
 	result.clear();
 	for (unsigned int i = 0; i < tbl.count(); i++) {
 		Item item(tbl[i].name(), pro[i]);
 		result.item(item);
 	}
+	this->add_impossible(result);
+}
+
+void Picker::calculate(unsigned int pick_amount, const std::vector<std::string>& names, Table& result) const
+{
+	result.clear();
+	if (m_table.count() == 0) return;
+	
+	Table tbl; this->effective_table(tbl);
+	double width = 0;
+	for (unsigned int i = 0; i < tbl.count(); i++)
+		width += tbl[i].value();
+	if (width == 0) return;
+	if (! m_table.repetitive_picking) check_amount("Picker::calculate()", pick_amount, tbl.count());
+	
+	std::vector<int> slot(tbl.count(), -1); //index in vect_pro of each item
+	std::vector<unsigned int> idx; std::vector<double> vect_pro;
+	for (unsigned int i = 0; i < tbl.count(); i++) {
+		for (unsigned int j = 0; j < names.size(); j++) {
+			if (tbl[i].name() != names[j]) continue;
+			slot[i] = idx.size(); idx.push_back(i); break;
+		}
+	}
+	vect_pro.resize(idx.size());
+	
+	if (pick_amount == 0) {
+		//nothing is picked, all probabilities are 0
+	} else if (tbl.repetitive_picking || pick_amount == 1) {
+		for (unsigned int i = 0; i < idx.size(); i++)
+			vect_pro[i] = tbl[idx[i]].value() / width;
+	} else if (pick_amount >= tbl.count()) {
+		for (unsigned int i = 0; i < idx.size(); i++)
+			vect_pro[i] = 1;
+	} else {
+		this->traverse(tbl, pick_amount, [&](const unsigned int* path, unsigned int dep, double pro) {
+			if (slot[path[dep]] >= 0) vect_pro[slot[path[dep]]] += pro;
+			return true;
+		});
+	}
+	
+	result.clear();
+	result.repetitive_picking = tbl.repetitive_picking;
+	for (unsigned int i = 0; i < idx.size(); i++) {
+		Item item(tbl[idx[i]].name(), vect_pro[i]);
+		result.item(item);
+	}
+	if (m_flag_keep_impossible) {
+		this->add_impossible(result);
+		result.retain([&](const std::string& name, double) {
+			return std::find(names.begin(), names.end(), name) != names.end();
+		});
+	}
+}
+
+void Picker::calculate_series(unsigned int max_amount, std::vector<Table>& result) const
+{
+	result.clear();
+	if (m_table.count() == 0) return;
+	
+	Table tbl; this->effective_table(tbl);
+	unsigned int cnt = tbl.count();
+	if (cnt == 0) return;
+	if (! m_table.repetitive_picking) check_amount("Picker::calculate_series()", max_amount, cnt);
+	
+	// the probability of an item in a group of k items is the sum of probabilities of being the (d+1)th item
+	// for d < k, which are got by a single traversal of the tree for the largest amount.
+	unsigned int depth = std::min(max_amount, cnt - 1);
+	std::vector<std::vector<double>> pro_at(depth, std::vector<double>(cnt));
+	if (! m_table.repetitive_picking) {
+		this->traverse(tbl, depth, [&](const unsigned int* path, unsigned int dep, double pro) {
+			pro_at[dep][path[dep]] += pro;
+			return true;
+		});
+	}
+	
+	std::vector<double> pro(cnt);
+	for (unsigned int k = 1; k <= max_amount; k++) {
+		Table cal;
+		if (m_table.repetitive_picking) {
+			this->calculate(k, cal); result.push_back(cal); continue;
+		}
+		
+		cal.repetitive_picking = tbl.repetitive_picking;
+		for (unsigned int i = 0; i < cnt; i++) {
+			if (k <= depth) pro[i] += pro_at[k - 1][i];
+			Item item(tbl[i].name(), (k < cnt)? pro[i] : 1.0);
+			cal.item(item);
+		}
+		this->add_impossible(cal);
+		result.push_back(cal);
+	}
+}
+
+void Picker::calculate_approx(unsigned int pick_amount, unsigned int samples, Table& result)
+{
+	result.clear();
+	if (pick_amount == 0) {this->calculate(0, result); return;}
+	if (samples == 0 || m_table.count() == 0) return;
+	
+	Table stat; this->test(samples, pick_amount, stat);
+	if (stat.is_empty()) return;
+	stat.repetitive_picking = m_table.repetitive_picking;
+	stat.scale(m_table.repetitive_picking? 1.0 / ((double)samples * pick_amount) : 1.0 / samples);
+	result = stat;
+}
+
+double Picker::expected_draws(unsigned int amount) const
+{
+	if (amount == 0) return 0;
+	if (m_table.repetitive_picking) return amount;
+	
+	Table tbl; this->effective_table(tbl);
+	check_amount("Picker::expected_draws()", amount, tbl.count());
+	
+	// after the items on a path are picked, a new item is got in width / (width - picked) draws on average.
+	double width = tbl.total_value(), sum = 1;
+	if (amount == 1) return sum;
+	this->traverse(tbl, amount - 1, [&](const unsigned int* path, unsigned int dep, double pro) {
+		double picked = 0;
+		for (unsigned int i = 0; i <= dep; i++)
+			picked += tbl[path[i]].value();
+		sum += pro * width / (width - picked);
+		return true;
+	});
+	return sum;
+}
+
+double Picker::calculate_complexity(unsigned int pick_amount) const
+{
+	Table tbl; this->effective_table(tbl);
+	unsigned int cnt = tbl.count();
+	if (pick_amount == 0 || cnt == 0) return 0;
+	if (tbl.repetitive_picking || pick_amount == 1 || pick_amount >= cnt) return cnt;
+	
+	double result = 1;
+	for (unsigned int i = 0; i < pick_amount; i++)
+		result *= cnt - i;
+	return result;
+}
+
+bool Picker::verify(unsigned int pick_amount, unsigned int times, double tolerance)
+{
+	Table cal, stat;
+	this->calculate(pick_amount, cal);
+	this->calculate_approx(pick_amount, times, stat);
+	if (cal.count() != stat.count()) return false;
+	
+	for (unsigned int i = 0; i < cal.count(); i++)
+		if (! (std::abs(cal[i].value() - stat.item_value(cal[i].name())) <= tolerance)) return false;
+	return true;
+}
+
+double Picker::prob_joint(const std::vector<std::string>& names, unsigned int amount) const
+{
+	Table tbl; this->effective_table(tbl);
+	if (! m_table.repetitive_picking) check_amount("Picker::prob_joint()", amount, tbl.count());
+	
+	std::vector<char> required(tbl.count()); //bool
+	unsigned int cnt_required = 0;
+	for (unsigned int i = 0; i < names.size(); i++) {
+		unsigned int j = 0;
+		while (j < tbl.count() && tbl[j].name() != names[i]) j++;
+		if (j == tbl.count()) return 0; //unknown or impossible item
+		if (! required[j]) {required[j] = true; cnt_required++;}
+	}
+	if (cnt_required == 0) return 1;
+	if (amount == 0) return 0;
+	
+	double width = 0;
+	for (unsigned int i = 0; i < tbl.count(); i++)
+		width += tbl[i].value();
+	
+	if (m_table.repetitive_picking) {
+		// inclusion-exclusion: sum of (-1)^|S| * (1 - P(S))^amount for each subset S of the items.
+		if (cnt_required > 20)
+			throw std::invalid_argument("Picker::prob_joint(): too many items.");
+		std::vector<double> pro;
+		for (unsigned int i = 0; i < tbl.count(); i++)
+			if (required[i]) pro.push_back(tbl[i].value() / width);
+		
+		double sum = 0;
+		for (unsigned long s = 0; s < (1ul << cnt_required); s++) {
+			double p = 0; int sign = 1;
+			for (unsigned int i = 0; i < cnt_required; i++)
+				if (s & (1ul << i)) {p += pro[i]; sign = -sign;}
+			sum += sign * std::pow(std::max(1.0 - p, 0.0), (double)amount);
+		}
+		return std::max(sum, 0.0);
+	}
+	
+	if (cnt_required > amount) return 0;
+	
+	double sum = 0;
+	this->traverse(tbl, amount, [&](const unsigned int* path, unsigned int dep, double pro) {
+		unsigned int found = 0;
+		for (unsigned int i = 0; i <= dep; i++)
+			if (required[path[i]]) found++;
+		if (found < cnt_required) return true;
+		sum += pro; return false; //all of the items are picked in this path
+	});
+	return sum;
+}
+
+double Picker::prob_at_position(const std::string& name, unsigned int position) const
+{
+	if (position == 0) throw std::invalid_argument("Picker::prob_at_position(): invalid position.");
+	Table tbl; this->effective_table(tbl);
+	if (! m_table.repetitive_picking) check_amount("Picker::prob_at_position()", position, tbl.count());
+	int k = -1;
+	for (unsigned int i = 0; i < tbl.count(); i++)
+		if (tbl[i].name() == name) {k = i; break;}
+	if (k < 0) return 0; //unknown or impossible item
+	
+	if (m_table.repetitive_picking) return tbl[k].value() / tbl.total_value();
+	
+	double sum = 0;
+	this->traverse(tbl, position, [&](const unsigned int* path, unsigned int dep, double pro) {
+		if (dep + 1 < position) return path[dep] != (unsigned int)k; //paths including the item are skipped
+		if (path[dep] == (unsigned int)k) sum += pro;
+		return false;
+	});
+	return sum;
+}
+
+double Picker::covariance(const std::string& a, const std::string& b, unsigned int amount) const
+{
+	if (! m_table.contains(a) || ! m_table.contains(b))
+		throw std::invalid_argument("Picker::covariance(): invalid item.");
+	Table tbl; this->effective_table(tbl);
+	if (! m_table.repetitive_picking) check_amount("Picker::covariance()", amount, tbl.count());
+	int ia = -1, ib = -1;
+	for (unsigned int i = 0; i < tbl.count(); i++) {
+		if (tbl[i].name() == a) ia = i;
+		if (tbl[i].name() == b) ib = i;
+	}
+	if (ia < 0 || ib < 0 || amount == 0) return 0; //an impossible item
+	
+	double width = 0;
+	for (unsigned int i = 0; i < tbl.count(); i++)
+		width += tbl[i].value();
+	
+	double pa = 0, pb = 0, pab = 0;
+	if (m_table.repetitive_picking) {
+		double qa = 1.0 - tbl[ia].value() / width, qb = 1.0 - tbl[ib].value() / width;
+		pa = 1.0 - std::pow(qa, (double)amount); pb = 1.0 - std::pow(qb, (double)amount);
+		pab = (ia == ib)? pa : 1.0 - std::pow(qa, (double)amount) - std::pow(qb, (double)amount)
+		                       + std::pow(std::max(qa + qb - 1.0, 0.0), (double)amount);
+	} else if (amount >= tbl.count()) {
+		pa = pb = pab = 1;
+	} else {
+		this->traverse(tbl, amount, [&](const unsigned int* path, unsigned int dep, double pro) {
+			int i = path[dep];
+			if (i == ia) pa += pro;
+			if (i == ib) pb += pro;
+			if (i != ia && i != ib) return true;
+			if (ia == ib) {pab += pro; return true;}
+			for (unsigned int j = 0; j < dep; j++) //the other one is picked before
+				if ((int)path[j] == ia || (int)path[j] == ib) {pab += pro; break;}
+			return true;
+		});
+	}
+	return pab - pa * pb;
+}
+
+bool Picker::inclusion_bounds(const std::string& name, double& lower, double& upper) const
+{
+	if (! m_table.contains(name)) return false;
+	lower = upper = 0;
+	
+	Table tbl; this->effective_table(tbl);
+	double width = 0;
+	for (unsigned int i = 0; i < tbl.count(); i++)
+		width += tbl[i].value();
+	if (width == 0 || tbl.item_value(name) == 0) return true;
+	
+	lower = tbl.item_value(name) / width;
+	if (m_table.repetitive_picking)
+		upper = 1.0 - std::pow(1.0 - lower, m_table.count());
+	else
+		upper = 1.0; //picking all possible items
+	return true;
+}
+
+void Picker::summary(std::ostream& ost) const
+{
+	Table tbl; this->effective_table(tbl);
+	double width = 0, min = 0, max = 0;
+	for (unsigned int i = 0; i < tbl.count(); i++) {
+		double val = tbl[i].value(); width += val;
+		if (i == 0 || val < min) min = val;
+		if (val > max) max = val;
+	}
+	
+	ost << "Picker{items: " << tbl.count()
+	    << ", repetitive: " << (m_table.repetitive_picking? "true" : "false")
+	    << ", total: " << width;
+	if (width > 0)
+		ost << ", p_min: " << min / width << ", p_max: " << max / width;
+	ost << '}';
+}
+
+bool Picker::output_dot_tree(unsigned int pick_amount, std::ostream& ost) const
+{
+	if (m_table.repetitive_picking)
+		throw std::invalid_argument("Picker::output_dot_tree(): repetitive picking is not supported.");
+	Table tbl; this->effective_table(tbl);
+	if (pick_amount == 0 || tbl.count() == 0) return false;
+	check_amount("Picker::output_dot_tree()", pick_amount, tbl.count());
+	
+	unsigned int nodes = 0, level = 1; //level: amount of nodes at current depth
+	for (unsigned int dep = 0; dep < pick_amount; dep++) {
+		level *= tbl.count() - dep; nodes += level;
+		if (nodes > Dot_Tree_Max_Nodes)
+			throw std::invalid_argument("Picker::output_dot_tree(): the tree is too large.");
+	}
+	
+	// the root node n0 stands for the state before picking;
+	// ids[dep] and pros[dep] are of the last visited node at depth dep.
+	std::vector<unsigned int> ids(pick_amount); std::vector<double> pros(pick_amount);
+	unsigned int id = 0;
+	
+	ost << "digraph tree {\n"
+	    << "\tn0 [label=\"1\"];\n";
+	this->traverse(tbl, pick_amount, [&](const unsigned int* path, unsigned int dep, double pro) {
+		unsigned int parent = (dep == 0)? 0 : ids[dep - 1];
+		double parent_pro = (dep == 0)? 1.0 : pros[dep - 1];
+		ids[dep] = ++id; pros[dep] = pro;
+		ost << "\tn" << id << " [label=\"" << tbl[path[dep]].name() << "\\n" << pro << "\"];\n"
+		    << "\tn" << parent << " -> n" << id << " [label=\"" << pro / parent_pro << "\"];\n";
+		return true;
+	});
+	ost << "}\n";
+	
+	return true;
+}
+
+void Picker::effective_table(Table& tbl) const
+{
+	// values are widths of cells in the grid, so that calculations match the grid used for picking exactly.
+	std::vector<double> grid; this->make_grid(grid);
+	tbl.clear();
+	tbl.repetitive_picking = m_table.repetitive_picking; tbl.power_inversed = false;
+	for (unsigned int i = 0; i < m_table.count(); i++) {
+		double width = grid[i + 1] - grid[i];
+		if (width <= 0) continue;
+		Item item(m_table[i].name(), width);
+		tbl.item(item);
+	}
+}
+
+void Picker::add_impossible(Table& result) const
+{
+	if (! m_flag_keep_impossible) return;
+	
+	Table tbl;
+	tbl.repetitive_picking = result.repetitive_picking; tbl.power_inversed = result.power_inversed;
+	for (unsigned int i = 0; i < m_table.count(); i++) {
+		Item item(m_table[i].name(), result.item_value(m_table[i].name()));
+		tbl.item(item);
+	}
+	result = tbl;
+}
+
+void Picker::traverse(const Table& tbl, unsigned int pick_amount,
+                      const std::function<bool(const unsigned int*, unsigned int, double)>& visit) const
+{
+	// visit() is called for each node in the tree of non-repetitive picking with the items
+	// picked from the root to the node, the depth of the node and the probability of the path;
+	// children of the node are skipped if visit() returns false.
+	if (pick_amount == 0 || tbl.count() == 0) return;
+	
+	unsigned int cnt = tbl.count();
+	std::vector<unsigned int> path(pick_amount);
+	std::vector<char> picked(cnt); //bool
+	std::vector<double> widths(pick_amount), pros(pick_amount);
+	
+	double width = 0;
+	for (unsigned int i = 0; i < cnt; i++)
+		width += tbl[i].value();
+	
+	TRACE_BEGIN();
+	std::function<void(unsigned int)> go = [&](unsigned int dep) {
+		double cur_width = (dep == 0)? width : widths[dep - 1];
+		double parent_pro = (dep == 0)? 1.0 : pros[dep - 1];
+		for (unsigned int i = 0; i < cnt; i++) {
+			if (picked[i]) continue;
+			TRACE_NODE();
+			path[dep] = i; pros[dep] = parent_pro * tbl[i].value() / cur_width;
+			if (! visit(path.data(), dep, pros[dep]) || dep + 1 >= pick_amount) continue;
+			picked[i] = true; widths[dep] = cur_width - tbl[i].value();
+			go(dep + 1);
+			picked[i] = false;
+		}
+	};
+	go(0);
+	TRACE_END("Picker::traverse()");
+}
+
+void RandomPicker::compare_rng_speed(Table& table, unsigned int amount, unsigned int times,
+                                     double& ms_device, double& ms_seeded)
+{
+	Table result;
+	Picker picker_device(table), picker_seeded(table, std::random_device()());
+	
+	auto t = std::chrono::steady_clock::now();
+	picker_device.test(times, amount, result);
+	ms_device = std::chrono::duration<double, std::milli>(std::chrono::steady_clock::now() - t).count();
+	
+	t = std::chrono::steady_clock::now();
+	picker_seeded.test(times, amount, result);
+	ms_seeded = std::chrono::duration<double, std::milli>(std::chrono::steady_clock::now() - t).count();
 }
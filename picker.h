@@ -7,6 +7,8 @@
 #include "table.h"
 
 #include <random>
+#include <functional>
+#include <ostream>
 
 namespace RandomPicker
 {
@@ -15,32 +17,286 @@ class Picker
 {
 	Table& m_table;
 	std::vector<double> m_grid;
+	std::vector<unsigned long long> m_int_grid; //used instead of m_grid if integer_grid(true) is set and values are integers
+	unsigned int m_cnt_possible = 0; //items of nonzero cells in the grid, counted by draw()
+	bool m_flag_integer = false;
+	bool m_flag_keep_impossible = false;
 	std::vector<char> m_vect_picked; //bool. char is used to avoid the specific implementation of vector<bool>.
 	std::random_device m_ran_dev;
+	std::mt19937 m_engine; //used instead of m_ran_dev after seed() is called
+	bool m_flag_seeded = false;
 	bool m_flag_testing = false;
+	double m_boundary_epsilon = 0;
+	std::function<void(unsigned int)> m_on_pick;
+	std::vector<unsigned int> m_indexes; //result of pick_indexes()
+	
+	std::vector<unsigned int> m_group; //picked in the current group
+	std::vector<char> m_group_picked; //bool, sized by the count of items when the group began
+	unsigned int m_group_possible = 0; //amount of possible items when the group began
+	double m_group_rest = 0; //width of the cells of items not picked in the current group
+	bool m_flag_group = false;
 	
 	double random_value(double width); //0.0 ~ width
+	double random_range() const; //max - min of the random source
+	unsigned long long random_bits(); //32 random bits
+	void make_grid(std::vector<double>& grid) const; //cumulative values (inversed if needed) of all items in the table
 	void draw(); //draw the grid for all items in the table
-	unsigned int determine(double val) const;
+	unsigned int determine(const std::vector<double>& grid, double val) const;
+	unsigned int determine_integer();
+	unsigned int draw_index(); //the grid should be drawn
+	void pick_to(unsigned int amount, unsigned int* dest); //the grid should be drawn
+	
+	void effective_table(Table& tbl) const; //widths of cells of possible items in the grid
+	void add_impossible(Table& result) const; //if keep_impossible(true) is set, in the order of the table
+	void traverse(const Table& tbl, unsigned int pick_amount,
+	              const std::function<bool(const unsigned int*, unsigned int, double)>& visit) const;
 	
 public:
 	Picker(Table& table);
+	Picker(Table& table, unsigned int seed);
+	
+	// copies settings of the other picker for the same table, with a random engine of its own:
+	// std::random_device, or std::mt19937 with the seed. the grid isn't copied because each pick draws it
+	// from the table again, and the current group is not copied.
+	Picker(const Picker& other);
+	Picker(const Picker& other, unsigned int seed);
+	
+	// makes the picker use std::mt19937 with the seed instead of std::random_device.
+	// the sequence of std::mt19937 is specified by the C++ standard, so that results are reproducible.
+	void seed(unsigned int seed);
+	
+	// boundaries in the grid closer than epsilon to a random value are regarded as coincident with it,
+	// then the item of the lowest index is chosen. it makes the result independent from rounding errors
+	// of the grid, at the cost of moving a tiny probability (epsilon / width) from each item to its previous one.
+	double boundary_epsilon() const;
+	bool boundary_epsilon(double eps);
+	
+	// picks items by integer arithmetic if values of the table are all integers (see Table::is_integral()),
+	// which avoids rounding errors of the grid; it is slower and makes a seeded picker give different results.
+	bool integer_grid() const;
+	void integer_grid(bool enabled);
+	
+	// keeps impossible items (of value 0) in results of test(), calculate() and calculate_approx() as items
+	// of value 0, so that results have the same items as the table. otherwise they are left out;
+	// the table itself and Table::count() are not affected.
+	bool keep_impossible() const;
+	void keep_impossible(bool enabled);
+	
+	// the callback is called with the index of each item drawn from the grid, including items rejected
+	// for being picked before in non-repetitive mode, and draws made by test(). it slows down picking.
+	void on_pick(const std::function<void(unsigned int)>& callback);
+	
+	[[nodiscard]] bool is_repetitive() const;
+	[[nodiscard]] double total_value() const; //width of the grid
+	
+	[[nodiscard]] bool contains(const std::string& name) const; //the item is possible to be picked
+	void items(std::vector<std::string>& result) const; //names of possible items
+	
+	// results are cleared before picking. an amount of 0 is valid everywhere: pick() gives an empty result,
+	// test() counts 0 for every possible item, calculate() and calculate_approx() give 0 for every possible item.
+	// std::runtime_error is thrown if a random value matches no item, which indicates a corrupted grid.
+	unsigned int pick_index(); //picks a single item
+	const std::vector<unsigned int>& pick_indexes(unsigned int amount); //valid until the next call, without copying
 	void pick(unsigned int amount, std::vector<unsigned int>& result);
 	void pick(unsigned int amount, std::vector<std::string>& result);
+	void pick(unsigned int amount, std::vector<const Item*>& result); //items in the table, invalid after it is modified
+	// maps a uniformly distributed 64-bit value from any source of randomness to the index of an item,
+	// the same way as picking with the grid (integer_grid() is not used). the random engine is not used.
+	// raw is mapped to val = total * raw / 2^64, then the item i of grid[i] <= val < grid[i+1] is chosen:
+	// a value exactly at a boundary belongs to the next possible item, 0 belongs to the first possible item,
+	// and a value rounded to the total (e.g. 2^64-1) belongs to the last possible item; impossible items are
+	// never chosen. see boundary_epsilon() for the behavior when epsilon is set.
+	[[nodiscard]] unsigned int index_of(unsigned long long raw) const;
+	
+	void pick_each(unsigned int amount, const std::function<void(unsigned int)>& callback); //gives indexes one by one
+	// picks groups one after another until the callback returns false, the vector is reused for each group.
+	void pick_chunks(unsigned int amount, const std::function<bool(const std::vector<unsigned int>&)>& callback);
+	void pick_groups(unsigned int groups, unsigned int amount, std::vector<unsigned int>& result); //groups one after another
+	
+	// picks groups until a group has at least min_distinct different items, returns false if max_tries groups
+	// are all rejected. std::invalid_argument is thrown if min_distinct exceeds the amount or possible items.
+	bool pick_min_distinct(unsigned int amount, unsigned int min_distinct, unsigned int max_tries,
+	                       std::vector<unsigned int>& result);
+	
+	// redraws the grid for the current table and drops the current group, returns the amount of possible items.
+	// it must be called if items are added to or removed from the table while a group is being picked.
+	unsigned int reset();
+	
+	// picks items of a group one by one without repetition, until finish_group() is called.
+	// draw_next() begins a group if there is no group, and throws std::logic_error if items are added to
+	// or removed from the table since the group began.
+	void begin_group();
+	unsigned int draw_next();
+	void finish_group(std::vector<unsigned int>& result);
+	void finish_group(std::vector<std::string>& result);
+	
+	// picks an item in the current group like drawing a card from a deck, and gets the probability
+	// of the remaining items in the deck, which decreases to 0 as the deck empties.
+	// begin_group() puts all items back into the deck.
+	std::string sample_and_remove();
+	std::string sample_and_remove(double& remaining);
+	
+	void replay(const std::vector<unsigned int>& indexes, std::vector<std::string>& result) const; //gets names of picked items
+	void cumulative(Table& result); //cumulative probabilities of items in the order of the table
+	void unreachable_items(std::vector<std::string>& result); //possible items too small for the precision of random values
+	
+	//picks all possible items without repetition, in a random order based on their probabilities.
+	void shuffle(std::vector<unsigned int>& result);
+	void shuffle(std::vector<std::string>& result);
+	
 	void test(unsigned int times, unsigned int amount, Table& result);
-	void calculate(unsigned int pick_amount, Table& result) const;
+	void calculate(unsigned int pick_amount, Table& result) const; //based on the same grid as picking
+	void calculate(unsigned int pick_amount, const std::vector<std::string>& names, Table& result) const; //only for these items
+	
+	// gets results of calculate() for amounts from 1 to max_amount (result[k - 1] is for amount k),
+	// by a single traversal which takes about the time of calculate(max_amount).
+	void calculate_series(unsigned int max_amount, std::vector<Table>& result) const;
+	
+	// estimates the result of calculate() by picking groups of items for the given times. the standard error
+	// of each probability is about sqrt(p*(1-p)/samples), while the time is proportional to samples * pick_amount;
+	// calculate() is exact but its time grows combinatorially with the table and the amount.
+	void calculate_approx(unsigned int pick_amount, unsigned int samples, Table& result);
+	
+	// amount of ordered groups of possible items that calculate() enumerates, n!/(n-k)! in non-repetitive mode,
+	// or n if it doesn't need to enumerate. it can be infinity if the number is too large for double.
+	[[nodiscard]] double calculate_complexity(unsigned int pick_amount) const;
+	
+	// expected amount of random values drawn from the grid for a group, including items rejected for
+	// being picked before in non-repetitive mode. it is as slow as calculate() for the same amount.
+	[[nodiscard]] double expected_draws(unsigned int amount) const;
+	
+	//checks if probabilities from test() are within the tolerance of probabilities from calculate().
+	[[nodiscard]] bool verify(unsigned int pick_amount, unsigned int times, double tolerance);
+	
+	//probability of all of the items being picked in a group.
+	[[nodiscard]] double prob_joint(const std::vector<std::string>& names, unsigned int amount) const;
+	
+	//probability of the item being the (position)th item picked in a group, position begins from 1.
+	[[nodiscard]] double prob_at_position(const std::string& name, unsigned int position) const;
+	
+	//covariance of occurences of the two items in a group: P(a and b) - P(a) * P(b).
+	[[nodiscard]] double covariance(const std::string& a, const std::string& b, unsigned int amount) const;
+	
+	//gets the range of probabilities of the item's occurence for pick amounts from 1 to the count of items.
+	bool inclusion_bounds(const std::string& name, double& lower, double& upper) const;
+	
+	//prints the decision tree of non-repetitive picking in Graphviz DOT language.
+	bool output_dot_tree(unsigned int pick_amount, std::ostream& ost) const;
+	
+	//prints a line like `Picker{items: 26, repetitive: false, total: 100, p_min: 0.0008, p_max: 0.12}`.
+	void summary(std::ostream& ost) const;
 };
 
 inline Picker::Picker(Table& table):
 	m_table(table)
 {}
 
+inline Picker::Picker(Table& table, unsigned int seed):
+	m_table(table)
+{
+	this->seed(seed);
+}
+
+inline Picker::Picker(const Picker& other):
+	m_table(other.m_table), m_flag_integer(other.m_flag_integer), m_flag_keep_impossible(other.m_flag_keep_impossible),
+	m_boundary_epsilon(other.m_boundary_epsilon), m_on_pick(other.m_on_pick)
+{}
+
+inline Picker::Picker(const Picker& other, unsigned int seed):
+	Picker(other)
+{
+	this->seed(seed);
+}
+
+inline void Picker::seed(unsigned int seed)
+{
+	m_engine.seed(seed); m_flag_seeded = true;
+}
+
+inline bool Picker::is_repetitive() const
+{
+	return m_table.repetitive_picking;
+}
+
+inline double Picker::total_value() const
+{
+	return m_table.total_value();
+}
+
+inline double Picker::boundary_epsilon() const
+{
+	return m_boundary_epsilon;
+}
+
+inline bool Picker::boundary_epsilon(double eps)
+{
+	if (! (eps >= 0)) return false;
+	m_boundary_epsilon = eps; return true;
+}
+
+inline bool Picker::integer_grid() const
+{
+	return m_flag_integer;
+}
+
+inline void Picker::integer_grid(bool enabled)
+{
+	m_flag_integer = enabled;
+}
+
+inline bool Picker::keep_impossible() const
+{
+	return m_flag_keep_impossible;
+}
+
+inline void Picker::keep_impossible(bool enabled)
+{
+	m_flag_keep_impossible = enabled;
+}
+
+inline void Picker::on_pick(const std::function<void(unsigned int)>& callback)
+{
+	m_on_pick = callback;
+}
+
 inline double Picker::random_value(double width)
 {
+	if (m_flag_seeded)
+		return width * (double)(m_engine() - m_engine.min()) / this->random_range();
+	
 	unsigned int val = m_ran_dev();
-	return width * (double)(val - m_ran_dev.min()) / (double)(m_ran_dev.max() - m_ran_dev.min());
+	return width * (double)(val - m_ran_dev.min()) / this->random_range();
+}
+
+inline unsigned long long Picker::random_bits()
+{
+	// ranges of std::random_device and std::mt19937 are both 0 ~ 2^32-1.
+	if (m_flag_seeded) return m_engine() - m_engine.min();
+	return m_ran_dev() - m_ran_dev.min();
 }
 
+inline double Picker::random_range() const
+{
+	if (m_flag_seeded)
+		return (double)(m_engine.max() - m_engine.min());
+	return (double)(m_ran_dev.max() - m_ran_dev.min());
+}
+
+inline std::ostream& operator<<(std::ostream& ost, const Picker& picker)
+{
+	picker.summary(ost); return ost;
+}
+
+//picks items from the table once with a picker seeded by the seed.
+inline void pick_seeded(Table& table, unsigned int amount, unsigned int seed, std::vector<std::string>& result)
+{
+	Picker picker(table, seed);
+	picker.pick(amount, result);
+}
+
+//measures the time (ms) of test() with std::random_device and with std::mt19937 respectively.
+void compare_rng_speed(Table& table, unsigned int amount, unsigned int times, double& ms_device, double& ms_seeded);
+
 }
 #endif
 
@@ -0,0 +1,22 @@
+// by wuwbobo2021 <https://github.com/wuwbobo2021>, <wuwbobo@outlook.com>
+// If you have found bugs in this program, please pull an issue, or contact me.
+
+#ifndef RAMDOM_PICKER_H
+#define RAMDOM_PICKER_H
+
+// includes everything needed to use the library, so that callers don't depend on the layout of its headers.
+#include "table.h"
+#include "picker.h"
+#include "reservoir.h"
+#include "weighted_index.h"
+#include "distribution.h"
+#include "stratified.h"
+
+#include <random>
+
+namespace RandomPicker
+{
+using Engine = std::mt19937; //the pseudo-random engine used by seeded pickers
+}
+
+#endif
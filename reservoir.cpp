@@ -0,0 +1,33 @@
+// by wuwbobo2021 <https://github.com/wuwbobo2021>, <wuwbobo@outlook.com>
+// If you have found bugs in this program, please pull an issue, or contact me.
+
+#include "reservoir.h"
+
+#include <algorithm>
+#include <cmath>
+
+using namespace RandomPicker;
+
+void Reservoir::push(const std::string& name, double value)
+{
+	if (value < 0 || ! std::isfinite(value))
+		throw std::invalid_argument("Reservoir::push(): invalid value.");
+	if (value == 0 || m_size == 0) return;
+	
+	double key = std::log(this->random_value()) / value; //log(u^(1/value)), it keeps the order of keys
+	if (m_heap.size() < m_size)
+		m_heap.push(Entry(key, name));
+	else if (key > m_heap.top().first) {
+		m_heap.pop(); m_heap.push(Entry(key, name));
+	}
+}
+
+void Reservoir::sample(std::vector<std::string>& result) const
+{
+	result.clear();
+	auto heap = m_heap;
+	while (! heap.empty()) {
+		result.push_back(heap.top().second); heap.pop();
+	}
+	std::reverse(result.begin(), result.end());
+}
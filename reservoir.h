@@ -0,0 +1,66 @@
+// by wuwbobo2021 <https://github.com/wuwbobo2021>, <wuwbobo@outlook.com>
+// If you have found bugs in this program, please pull an issue, or contact me.
+
+#ifndef RAMDOM_PICKER_RESERVOIR_H
+#define RAMDOM_PICKER_RESERVOIR_H
+
+#include <random>
+#include <queue>
+#include <stdexcept>
+#include <string>
+#include <utility>
+#include <vector>
+
+namespace RandomPicker
+{
+
+// picks items without repetition from a stream of items which is too large to be stored in a table,
+// by the A-Res algorithm: each item gets a key u^(1/value), and items of the largest keys are kept.
+class Reservoir
+{
+	typedef std::pair<double, std::string> Entry; //key, name
+	
+	unsigned int m_size;
+	std::priority_queue<Entry, std::vector<Entry>, std::greater<Entry>> m_heap; //the smallest key at the top
+	std::random_device m_ran_dev;
+	
+	double random_value(); //0.0 ~ 1.0
+	
+public:
+	Reservoir(unsigned int size);
+	
+	unsigned int size() const;
+	unsigned int count() const;
+	
+	void push(const std::string& name, double value);
+	void sample(std::vector<std::string>& result) const; //in the order of picking
+	void clear();
+};
+
+inline Reservoir::Reservoir(unsigned int size):
+	m_size(size)
+{}
+
+inline unsigned int Reservoir::size() const
+{
+	return m_size;
+}
+
+inline unsigned int Reservoir::count() const
+{
+	return m_heap.size();
+}
+
+inline void Reservoir::clear()
+{
+	m_heap = decltype(m_heap)();
+}
+
+inline double Reservoir::random_value()
+{
+	unsigned int val = m_ran_dev();
+	return (double)(val - m_ran_dev.min()) / (double)(m_ran_dev.max() - m_ran_dev.min());
+}
+
+}
+#endif
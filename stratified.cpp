@@ -0,0 +1,70 @@
+// by wuwbobo2021 <https://github.com/wuwbobo2021>, <wuwbobo@outlook.com>
+// If you have found bugs in this program, please pull an issue, or contact me.
+
+#include "stratified.h"
+
+#include <stdexcept>
+#include <tuple>
+
+using namespace RandomPicker;
+
+StratifiedPicker::StratifiedPicker(Table& categories, std::map<std::string, Table>& items):
+	m_categories(categories), m_items(items), m_picker(categories)
+{
+	this->check_categories();
+	for (auto it = m_items.begin(); it != m_items.end(); ++it)
+		m_item_pickers.emplace(std::piecewise_construct, std::forward_as_tuple(it->first),
+		                       std::forward_as_tuple(it->second));
+}
+
+StratifiedPicker::StratifiedPicker(Table& categories, std::map<std::string, Table>& items, unsigned int seed):
+	m_categories(categories), m_items(items), m_picker(categories, seed)
+{
+	this->check_categories();
+	unsigned int i = 1;
+	for (auto it = m_items.begin(); it != m_items.end(); ++it, i++) //each picker gets a different seed
+		m_item_pickers.emplace(std::piecewise_construct, std::forward_as_tuple(it->first),
+		                       std::forward_as_tuple(it->second, seed + i));
+}
+
+void StratifiedPicker::check_categories() const
+{
+	if (! m_categories.check())
+		throw std::invalid_argument("StratifiedPicker::StratifiedPicker(): invalid table of categories.");
+	
+	for (unsigned int i = 0; i < m_categories.count(); i++) {
+		if (m_categories[i].value() == 0) continue;
+		auto it = m_items.find(m_categories[i].name());
+		if (it == m_items.end() || ! it->second.check())
+			throw std::invalid_argument("StratifiedPicker::StratifiedPicker(): invalid category \""
+			                            + m_categories[i].name() + "\".");
+	}
+}
+
+void StratifiedPicker::pick(std::string& category, std::string& item)
+{
+	category = m_categories[m_picker.pick_index()].name();
+	Table& items = m_items.at(category);
+	item = items[m_item_pickers.at(category).pick_index()].name();
+}
+
+void StratifiedPicker::pick(unsigned int amount, std::vector<std::pair<std::string, std::string>>& result)
+{
+	result.resize(amount);
+	for (unsigned int i = 0; i < amount; i++)
+		this->pick(result[i].first, result[i].second);
+}
+
+void StratifiedPicker::flatten(Table& result) const
+{
+	Table cat; m_categories.probabilities(cat);
+	result.clear();
+	result.repetitive_picking = true; result.power_inversed = false;
+	for (unsigned int i = 0; i < cat.count(); i++) {
+		Table pro; m_items.at(cat[i].name()).probabilities(pro);
+		for (unsigned int j = 0; j < pro.count(); j++) {
+			Item item(cat[i].name() + "__" + pro[j].name(), cat[i].value() * pro[j].value());
+			result.item(item);
+		}
+	}
+}
@@ -0,0 +1,43 @@
+// by wuwbobo2021 <https://github.com/wuwbobo2021>, <wuwbobo@outlook.com>
+// If you have found bugs in this program, please pull an issue, or contact me.
+
+#ifndef RAMDOM_PICKER_STRATIFIED_H
+#define RAMDOM_PICKER_STRATIFIED_H
+
+#include "table.h"
+#include "picker.h"
+
+#include <map>
+#include <string>
+#include <utility>
+#include <vector>
+
+namespace RandomPicker
+{
+
+// picks a category by values in the table of categories, then picks an item in the table of that category,
+// so the probability of an item is P(category) * P(item in the category). each draw is independent,
+// flags of the tables are not used except power_inversed. the tables should be valid and not be removed
+// while the picker is in use; each possible category should have a table of possible items.
+class StratifiedPicker
+{
+	Table& m_categories;
+	std::map<std::string, Table>& m_items;
+	Picker m_picker;
+	std::map<std::string, Picker> m_item_pickers;
+	
+	void check_categories() const;
+	
+public:
+	StratifiedPicker(Table& categories, std::map<std::string, Table>& items);
+	StratifiedPicker(Table& categories, std::map<std::string, Table>& items, unsigned int seed);
+	
+	void pick(std::string& category, std::string& item);
+	void pick(unsigned int amount, std::vector<std::pair<std::string, std::string>>& result); //category, item
+	
+	//probabilities of all possible items as a flat table, in which names are like `category__item`.
+	void flatten(Table& result) const;
+};
+
+}
+#endif
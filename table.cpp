@@ -3,8 +3,12 @@
 
 #include "table.h"
 
+#include <algorithm>
 #include <cctype>
+#include <cmath>
 #include <fstream>
+#include <limits>
+#include <sstream>
 
 using namespace RandomPicker;
 
@@ -34,22 +38,380 @@ int Table::find_name(const std::string& name) const
 	return -1;
 }
 
-void Table::remove_impossible()
+static double entropy_of(const double* vals, unsigned int cnt)
+{
+	double total = 0, ent = 0;
+	for (unsigned int i = 0; i < cnt; i++)
+		total += vals[i];
+	for (unsigned int i = 0; i < cnt; i++)
+		if (vals[i] > 0) ent -= vals[i] / total * std::log2(vals[i] / total);
+	return ent;
+}
+
+bool Table::check(std::string& name, std::string& reason) const
+{
+	bool possible = false;
+	for (unsigned int i = 0; i < m_count; i++) {
+		double val = m_vect[i].value();
+		if (std::isnan(val)) {
+			name = m_vect[i].name(); reason = "not a number"; return false;
+		}
+		if (std::isinf(val)) {
+			name = m_vect[i].name(); reason = "infinite value"; return false;
+		}
+		if (val < 0) {
+			name = m_vect[i].name(); reason = "negative value"; return false;
+		}
+		if (val > 0) possible = true;
+	}
+	
+	if (! possible) {
+		name = ""; reason = "no possible item"; return false;
+	}
+	
+	// the sum of values (inversed if power_inversed is set) must not overflow in the grid.
+	double max = std::numeric_limits<double>::max() / m_count;
+	for (unsigned int i = 0; i < m_count; i++) {
+		if (this->effective_value(i) > max) {
+			name = m_vect[i].name(); reason = "value too large"; return false;
+		}
+	}
+	return true;
+}
+
+void Table::grid(std::vector<double>& result) const
+{
+	result.clear();
+	if (m_count == 0) return;
+	
+	double cur = 0;
+	for (unsigned int i = 0; i < m_count; i++) {
+		result.push_back(cur);
+		cur += this->effective_value(i);
+	}
+	result.push_back(cur);
+}
+
+unsigned int Table::count_possible(const std::vector<double>& grid)
+{
+	unsigned int cnt = 0;
+	for (unsigned int i = 0; i + 1 < grid.size(); i++)
+		if (grid[i + 1] > grid[i]) cnt++;
+	return cnt;
+}
+
+unsigned int Table::count_possible() const
+{
+	std::vector<double> vect; this->grid(vect);
+	return Table::count_possible(vect);
+}
+
+bool Table::validate_amount(unsigned int amount, std::string& reason) const
+{
+	std::string name;
+	if (! this->check(name, reason)) {
+		if (name.length() > 0) reason = "invalid item \"" + name + "\": " + reason;
+		return false;
+	}
+	if (this->repetitive_picking) return true;
+	
+	unsigned int cnt = this->count_possible();
+	if (amount > cnt) {
+		reason = "amount " + std::to_string(amount) + " exceeds " + std::to_string(cnt) + " possible items";
+		return false;
+	}
+	return true;
+}
+
+double Table::total_value() const
+{
+	double total = 0;
+	for (unsigned int i = 0; i < m_count; i++) {
+		double val = this->effective_value(i);
+		if (val > 0 && std::isfinite(val)) total += val;
+	}
+	return total;
+}
+
+double Table::min_value() const
+{
+	double min = 0;
+	for (unsigned int i = 0; i < m_count; i++) {
+		double val = this->effective_value(i);
+		if (val > 0 && std::isfinite(val) && (min == 0 || val < min)) min = val;
+	}
+	return min;
+}
+
+double Table::max_value() const
+{
+	double max = 0;
+	for (unsigned int i = 0; i < m_count; i++) {
+		double val = this->effective_value(i);
+		if (val > max && std::isfinite(val)) max = val;
+	}
+	return max;
+}
+
+double Table::value_ratio() const
+{
+	double min = this->min_value();
+	if (min == 0) return 0;
+	return this->max_value() / min;
+}
+
+bool Table::is_integral() const
+{
+	double total = 0;
+	for (unsigned int i = 0; i < m_count; i++) {
+		double val = this->effective_value(i);
+		if (val != std::floor(val)) return false;
+		total += val;
+	}
+	return total > 0 && total < 9007199254740992.0; //2^53
+}
+
+void Table::probabilities(Table& result) const
+{
+	if (! this->check())
+		throw std::invalid_argument("Table::probabilities(): invalid table.");
+	
+	double total = this->total_value();
+	result.clear();
+	result.repetitive_picking = this->repetitive_picking;
+	result.power_inversed = false;
+	for (unsigned int i = 0; i < m_count; i++) {
+		double val = this->effective_value(i);
+		if (val == 0) continue;
+		Item item(m_vect[i].name(), val / total);
+		result.item(item);
+	}
+}
+
+double Table::entropy() const
+{
+	if (! this->check())
+		throw std::invalid_argument("Table::entropy(): invalid table.");
+	
+	std::vector<double> vals(m_count);
+	for (unsigned int i = 0; i < m_count; i++)
+		vals[i] = this->effective_value(i);
+	return entropy_of(vals.data(), m_count);
+}
+
+double Table::prob_before(const std::string& a, const std::string& b) const
+{
+	int ia = this->find_name(a), ib = this->find_name(b);
+	if (ia < 0 || ib < 0 || ia == ib)
+		throw std::invalid_argument("Table::prob_before(): invalid item.");
+	
+	double va = this->effective_value(ia), vb = this->effective_value(ib);
+	if (! (va > 0 && vb > 0))
+		throw std::invalid_argument("Table::prob_before(): impossible item.");
+	return va / (va + vb);
+}
+
+unsigned int Table::items_for_mass(double target) const
+{
+	if (! (target >= 0 && target <= 1))
+		throw std::invalid_argument("Table::items_for_mass(): invalid target.");
+	if (! this->check())
+		throw std::invalid_argument("Table::items_for_mass(): invalid table.");
+	
+	std::vector<double> vals(m_count);
+	for (unsigned int i = 0; i < m_count; i++)
+		vals[i] = this->effective_value(i);
+	std::sort(vals.begin(), vals.end(), [](double a, double b) {return a > b;});
+	
+	double total = 0;
+	for (unsigned int i = 0; i < m_count; i++)
+		total += vals[i];
+	
+	double cur = 0;
+	for (unsigned int i = 0; i < m_count; i++) {
+		if (cur >= target * total) return i;
+		cur += vals[i];
+	}
+	return m_count;
+}
+
+bool Table::is_fair(double eps) const
+{
+	if (m_count == 0) return false;
+	
+	double first = m_vect[0].value();
+	for (unsigned int i = 1; i < m_count; i++) {
+		double val = m_vect[i].value();
+		if (std::abs(val - first) > eps * std::max(val, first)) return false;
+	}
+	return true;
+}
+
+bool Table::passes_fairness(double max_deviation) const
+{
+	if (! this->check()) return false;
+	
+	double total = this->total_value();
+	
+	for (unsigned int i = 0; i < m_count; i++)
+		if (std::abs(this->effective_value(i) / total - 1.0 / m_count) > max_deviation)
+			return false;
+	return true;
+}
+
+void Table::max_entropy_subset(unsigned int k, Table& result) const
+{
+	// heuristic: the items are sorted by their probabilities, and the subset is chosen from
+	// groups of neighbouring items in the sorted sequence, which avoids mixing up dominant and
+	// negligible items; it is not guaranteed to be the best among all combinations.
+	if (! this->check())
+		throw std::invalid_argument("Table::max_entropy_subset(): invalid table.");
+	
+	std::vector<unsigned int> idx;
+	for (unsigned int i = 0; i < m_count; i++)
+		if (this->effective_value(i) > 0) idx.push_back(i);
+	if (k == 0 || k > idx.size())
+		throw std::invalid_argument("Table::max_entropy_subset(): invalid amount.");
+	
+	std::stable_sort(idx.begin(), idx.end(), [&](unsigned int a, unsigned int b) {
+		return this->effective_value(a) < this->effective_value(b);
+	});
+	std::vector<double> vals(idx.size());
+	for (unsigned int i = 0; i < idx.size(); i++)
+		vals[i] = this->effective_value(idx[i]);
+	
+	unsigned int best = 0; double best_ent = -1;
+	for (unsigned int i = 0; i + k <= idx.size(); i++) {
+		double ent = entropy_of(vals.data() + i, k);
+		if (ent > best_ent) {best = i; best_ent = ent;}
+	}
+	
+	std::vector<char> chosen(m_count); //bool
+	for (unsigned int i = best; i < best + k; i++)
+		chosen[idx[i]] = true;
+	
+	result.clear();
+	result.repetitive_picking = this->repetitive_picking;
+	result.power_inversed = this->power_inversed;
+	for (unsigned int i = 0; i < m_count; i++) {
+		if (! chosen[i]) continue;
+		Item item = m_vect[i];
+		result.item(item);
+	}
+}
+
+void Table::sort()
+{
+	std::sort(m_vect.begin(), m_vect.end(), [](const Item& a, const Item& b) {return a.name() < b.name();});
+}
+
+void Table::merge(const Table& other)
+{
+	if (this->power_inversed != other.power_inversed)
+		throw std::invalid_argument("Table::merge(): power_inversed flags are different.");
+	
+	for (unsigned int i = 0; i < other.m_count; i++) {
+		const Item& it = other.m_vect[i];
+		Item item(it.name(), this->item_value(it.name()) + it.value());
+		this->item(item);
+	}
+}
+
+void Table::retain(const std::function<bool(const std::string&, double)>& pred)
 {
 	for (unsigned int i = 0; i < m_count; i++) {
-		if (m_vect[i].value() == 0) {
+		if (! pred(m_vect[i].name(), m_vect[i].value())) {
 			m_vect.erase(m_vect.begin() + i);
 			m_count--; i--; //stay at the previous position in the next loop
 		}
 	}
 }
 
-void Table::scale(float scaler)
+void Table::refresh(const std::function<double(const std::string&)>& weight_fn)
+{
+	// all values are checked before any of them is changed, so the table is untouched on failure
+	std::vector<Item> vect(m_vect.begin(), m_vect.begin() + m_count);
+	for (unsigned int i = 0; i < m_count; i++)
+		if (! vect[i].value(weight_fn(vect[i].name())))
+			throw std::invalid_argument("Table::refresh(): invalid value for item \"" + vect[i].name() + "\".");
+	
+	std::copy(vect.begin(), vect.end(), m_vect.begin());
+}
+
+void Table::prune_below(double min_value)
+{
+	this->retain([=](const std::string&, double val) {return val >= min_value;});
+}
+
+void Table::top_k(unsigned int k)
 {
+	if (k >= m_count) return;
+	
+	std::vector<unsigned int> idx(m_count);
+	for (unsigned int i = 0; i < m_count; i++)
+		idx[i] = i;
+	std::sort(idx.begin(), idx.end(), [&](unsigned int a, unsigned int b) {
+		double va = this->effective_value(a), vb = this->effective_value(b);
+		if (va != vb) return va > vb;
+		return m_vect[a].name() < m_vect[b].name();
+	});
+	
+	std::vector<char> kept(m_count); //bool
+	for (unsigned int i = 0; i < k; i++)
+		kept[idx[i]] = true;
+	
+	std::vector<Item> vect;
+	for (unsigned int i = 0; i < m_count; i++)
+		if (kept[i]) vect.push_back(m_vect[i]);
+	m_vect = vect; m_count = k;
+}
+
+void Table::subtract(const Table& other)
+{
+	if (this->power_inversed != other.power_inversed)
+		throw std::invalid_argument("Table::subtract(): power_inversed flags are different.");
+	
+	for (unsigned int i = 0; i < other.m_count; i++) {
+		const Item& it = other.m_vect[i];
+		Item item(it.name(), std::max(this->item_value(it.name()) - it.value(), 0.0));
+		this->item(item);
+	}
+}
+
+void Table::remove_impossible()
+{
+	this->retain([](const std::string&, double val) {return val != 0;});
+}
+
+void Table::scale(double scaler)
+{
+	if (! (scaler > 0) || ! std::isfinite(scaler))
+		throw std::invalid_argument("Table::scale(): invalid scaler.");
+	for (unsigned int i = 0; i < m_count; i++)
+		if (! std::isfinite(scaler * m_vect[i].value()))
+			throw std::invalid_argument("Table::scale(): overflow.");
+	
 	for (unsigned int i = 0; i < m_count; i++)
 		m_vect[i].value(scaler * m_vect[i].value());
 }
 
+void Table::normalize()
+{
+	this->normalize(1.0);
+}
+
+void Table::normalize(double target)
+{
+	if (! this->check())
+		throw std::invalid_argument("Table::normalize(): invalid table.");
+	if (! (target > 0) || ! std::isfinite(target))
+		throw std::invalid_argument("Table::normalize(): invalid target.");
+	
+	double total = this->total_value();
+	
+	this->scale(this->power_inversed? total / target : target / total);
+}
+
 void Table::inverse()
 {
 	for (unsigned int i = 0; i < m_count; i++)
@@ -59,38 +421,91 @@ void Table::inverse()
 	this->power_inversed = ! this->power_inversed;
 }
 
-bool Table::input(std::istream& ist)
+// separates `=`, `+=`, `-=`, `*=` and `/=` from names and values by spaces.
+static std::string split_operators(const std::string& line)
 {
-	std::string name; double val; Item it("none", 0);
-	while (! ist.eof()) {
-		ist >> name;
-		if (! ist) return true;
-		
-		if (name == String_End_Of_Input) return true;
-		else if (name == String_Delete) {
-			ist >> name; if (! ist) return true;
-			unsigned int i = this->find_name(name);
-			if (i < 0) continue;
-			m_vect.erase(m_vect.begin() + i); m_count--; continue;
-		}
-		else if (name == String_Repetitive_Picking) {
-			this->repetitive_picking = true; continue;
-		} else if (name == String_Power_Inversed) {
-			this->power_inversed = true; continue;
-		}
-		
-		ist >> val;
-		if (! ist) {ist.clear(); return false;}
-		
-		try {
-			it = Item(name, val);
-			this->item(it);
-		} catch (std::invalid_argument ex) {
-			return false;
+	std::string str;
+	for (unsigned int i = 0; i < line.length(); i++) {
+		if (i + 1 < line.length() && line[i + 1] == '=' && std::string("+-*/").find(line[i]) != std::string::npos) {
+			str += ' '; str += line[i]; str += "= "; i++;
+		} else if (line[i] == '=')
+			str += " = ";
+		else
+			str += line[i];
+	}
+	return str;
+}
+
+bool Table::read(std::istream& ist, const InputOptions& opts, std::vector<InputError>& errors, bool additive)
+{
+	errors.clear();
+	std::string line, token, name; double val; Item it("none", 0);
+	unsigned int line_num = 0, name_line_num = 0; std::string name_line; //where the name waiting for its value is
+	bool flag_delete = false, flag_value = false; //waiting for the name to be deleted, or the value of the item
+	char op = 0; //operator between the name and the value
+	
+	while (std::getline(ist, line)) {
+		line_num++;
+		std::string str = line;
+		if (! opts.comment_prefix.empty()) str = str.substr(0, str.find(opts.comment_prefix));
+		if (opts.separator != 0) std::replace(str.begin(), str.end(), opts.separator, ' ');
+		std::istringstream sst(split_operators(str));
+		while (sst >> token) {
+			if (flag_delete) {
+				int i = this->find_name(token);
+				if (i >= 0) {m_vect.erase(m_vect.begin() + i); m_count--;}
+				flag_delete = false; continue;
+			}
+			
+			if (flag_value && token.back() == '=' && op == 0) {
+				op = token[0]; //`=` can be omitted
+				if (token.length() > 1 && op != '+' && ! this->contains(name)) { //only `+=` works for a new item
+					errors.push_back(InputError{line_num, line}); flag_value = false;
+					sst >> token; //skip the value
+				}
+				continue;
+			}
+			
+			if (flag_value) {
+				flag_value = false;
+				std::istringstream sst_val(token);
+				if (! (sst_val >> val) || ! sst_val.eof() || (op == 0 && opts.equal_required)) {
+					errors.push_back(InputError{line_num, line}); continue;
+				}
+				double cur = this->item_value(name);
+				switch (op) {
+					case '+': val = cur + val; break;
+					case '-': val = cur - val; break;
+					case '*': val = cur * val; break;
+					case '/': val = (val != 0)? cur / val : -1; break; //invalid value
+					default: if (additive) val += cur; break;
+				}
+				try {
+					it = Item(name, val);
+					this->item(it);
+				} catch (std::invalid_argument ex) {
+					errors.push_back(InputError{line_num, line});
+				}
+				continue;
+			}
+			
+			if (token == String_End_Of_Input) return errors.empty();
+			else if (token == String_Delete)
+				flag_delete = true;
+			else if (token == String_Repetitive_Picking)
+				this->repetitive_picking = true;
+			else if (token == String_Power_Inversed)
+				this->power_inversed = true;
+			else {
+				name = token; flag_value = true; op = 0;
+				name_line_num = line_num; name_line = line;
+			}
 		}
 	}
 	
-	return true;
+	if (flag_value) //the value may be on a following line, but it's missing
+		errors.push_back(InputError{name_line_num, name_line});
+	return errors.empty();
 }
 
 bool Table::output(std::ostream& ost) const
@@ -109,12 +524,140 @@ bool Table::output(std::ostream& ost) const
 	return true;
 }
 
+// reads a JSON object like {"repetitive_picking": false, "power_inversed": false, "items": {"a": 1, "b": 2}},
+// in which the two flags are optional.
+bool Table::input_json(std::istream& ist)
+{
+	auto expect = [&](char ch) -> bool {
+		ist >> std::ws; if (ist.peek() != ch) return false;
+		ist.get(); return true;
+	};
+	auto read_key = [&](std::string& key) -> bool {
+		if (! expect('"')) return false;
+		std::getline(ist, key, '"');
+		return ist && expect(':');
+	};
+	auto read_bool = [&](bool& val) -> bool {
+		std::string str; ist >> std::ws;
+		while (isalpha(ist.peek())) str.push_back(ist.get());
+		if (str == "true") val = true;
+		else if (str == "false") val = false;
+		else return false;
+		return true;
+	};
+	
+	std::string key, name; double val; Item it("none", 0);
+	if (! expect('{')) return false;
+	if (expect('}')) return true;
+	do {
+		if (! read_key(key)) return false;
+		if (key == String_Repetitive_Picking) {
+			if (! read_bool(this->repetitive_picking)) return false;
+		} else if (key == String_Power_Inversed) {
+			if (! read_bool(this->power_inversed)) return false;
+		} else if (key == "items") {
+			if (! expect('{')) return false;
+			if (expect('}')) continue;
+			do {
+				if (! read_key(name)) return false;
+				ist >> val; if (! ist) {ist.clear(); return false;}
+				try {
+					it = Item(name, val);
+					this->item(it);
+				} catch (std::invalid_argument ex) {
+					return false;
+				}
+			} while (expect(','));
+			if (! expect('}')) return false;
+		} else
+			return false;
+	} while (expect(','));
+	
+	return expect('}');
+}
+
+bool Table::output_json(std::ostream& ost) const
+{
+	std::streamsize prec = ost.precision(std::numeric_limits<double>::max_digits10);
+	ost << "{\"" << String_Repetitive_Picking << "\": " << (this->repetitive_picking? "true" : "false")
+	    << ", \"" << String_Power_Inversed << "\": " << (this->power_inversed? "true" : "false")
+	    << ", \"items\": {";
+	for (unsigned int i = 0; i < m_count; i++) {
+		if (i > 0) ost << ", ";
+		ost << '"' << m_vect[i].name() << "\": " << m_vect[i].value();
+	}
+	ost << "}}\n";
+	ost.precision(prec);
+	
+	return true;
+}
+
+// reads the flags in section [random-picker] and items in section [items], in the form of `key = value`.
+bool Table::input_toml(std::istream& ist)
+{
+	std::string line, section, key, str; double val; Item it("none", 0);
+	while (std::getline(ist, line)) {
+		std::size_t pos = line.find('=');
+		if (pos != std::string::npos) line.replace(pos, 1, " = ");
+		
+		std::istringstream sst(line);
+		if (! (sst >> key) || key[0] == '#') continue;
+		if (key[0] == '[') {
+			if (key == "[random-picker]" || key == "[items]") section = key;
+			else return false;
+			continue;
+		}
+		if (! (sst >> str) || str != "=") return false;
+		
+		if (section == "[random-picker]") {
+			if (! (sst >> str)) return false;
+			if (str != "true" && str != "false") return false;
+			if (key == String_Repetitive_Picking) this->repetitive_picking = (str == "true");
+			else if (key == String_Power_Inversed) this->power_inversed = (str == "true");
+			else return false;
+		} else if (section == "[items]") {
+			if (! (sst >> val)) return false;
+			try {
+				it = Item(key, val);
+				this->item(it);
+			} catch (std::invalid_argument ex) {
+				return false;
+			}
+		} else
+			return false;
+		
+		if (sst >> str && str[0] != '#') return false;
+	}
+	
+	return true;
+}
+
+bool Table::output_toml(std::ostream& ost) const
+{
+	std::streamsize prec = ost.precision(std::numeric_limits<double>::max_digits10);
+	ost << "[random-picker]\n"
+	    << String_Repetitive_Picking << " = " << (this->repetitive_picking? "true" : "false") << '\n'
+	    << String_Power_Inversed << " = " << (this->power_inversed? "true" : "false") << "\n\n"
+	    << "[items]\n";
+	for (unsigned int i = 0; i < m_count; i++)
+		ost << m_vect[i].name() << " = " << m_vect[i].value() << '\n';
+	ost.precision(prec);
+	
+	return true;
+}
+
 bool Table::open(const std::string& path)
 {
-	this->clear();
+	std::vector<InputError> errors;
+	return this->open(path, errors);
+}
+
+bool Table::open(const std::string& path, std::vector<InputError>& errors)
+{
+	this->clear(); errors.clear();
 	std::ifstream ifs(path, std::ios_base::in);
 	if (! ifs.is_open()) return false;
-	return this->input(ifs);
+	return this->input(ifs, errors);
 }
 
 bool Table::save(const std::string& path) const
@@ -125,3 +668,53 @@ bool Table::save(const std::string& path) const
 	return this->output(ofs);
 }
 
+
+double RandomPicker::chi_square(const Table& observed, const Table& expected, unsigned int n)
+{
+	double sum = 0;
+	for (unsigned int i = 0; i < expected.count(); i++) {
+		double e = n * expected[i].value();
+		if (e == 0) continue;
+		double o = observed.item_value(expected[i].name());
+		sum += (o - e) * (o - e) / e;
+	}
+	return sum;
+}
+
+static double sum_of_values(const Table& tbl)
+{
+	double sum = 0;
+	for (unsigned int i = 0; i < tbl.count(); i++)
+		sum += tbl[i].value();
+	return sum;
+}
+
+double RandomPicker::kl_divergence(const Table& p, const Table& q)
+{
+	double sp = sum_of_values(p), sq = sum_of_values(q);
+	if (sp == 0 || sq == 0)
+		throw std::invalid_argument("RandomPicker::kl_divergence(): invalid table.");
+	
+	double sum = 0;
+	for (unsigned int i = 0; i < p.count(); i++) {
+		double vp = p[i].value() / sp, vq = q.item_value(p[i].name()) / sq;
+		if (vp == 0) continue;
+		if (vq == 0) return std::numeric_limits<double>::infinity();
+		sum += vp * std::log(vp / vq);
+	}
+	return sum;
+}
+
+double RandomPicker::total_variation(const Table& p, const Table& q)
+{
+	double sp = sum_of_values(p), sq = sum_of_values(q);
+	if (sp == 0 || sq == 0)
+		throw std::invalid_argument("RandomPicker::total_variation(): invalid table.");
+	
+	double sum = 0;
+	for (unsigned int i = 0; i < p.count(); i++)
+		sum += std::abs(p[i].value() / sp - q.item_value(p[i].name()) / sq);
+	for (unsigned int i = 0; i < q.count(); i++)
+		if (! p.contains(q[i].name())) sum += q[i].value() / sq;
+	return sum / 2;
+}
@@ -4,9 +4,14 @@
 #ifndef RAMDOM_PICKER_TABLE_H
 #define RAMDOM_PICKER_TABLE_H
 
+#include <cmath>
 #include <stdexcept>
 #include <vector>
 #include <string>
+#include <utility>
+#include <initializer_list>
+#include <functional>
+#include <ostream>
 
 namespace RandomPicker
 {
@@ -31,7 +36,7 @@ inline Item::Item(const std::string& name, double value)
 	if (! this->name(name))
 		throw std::invalid_argument("RandomPicker::Item::Item(): invalid name.");
 	if (! this->value(value))
-		throw std::invalid_argument("RandomPicker::Item::Item(): minus value.");
+		throw std::invalid_argument("RandomPicker::Item::Item(): invalid value.");
 	
 	m_value = value;
 }
@@ -48,38 +53,141 @@ inline double Item::value() const
 
 inline bool Item::value(double value)
 {
-	if (value < 0) return false;
+	if (value < 0 || ! std::isfinite(value)) return false;
 	m_value = value; return true;
 }
 
+struct InputError
+{
+	unsigned int line; //starts from 1
+	std::string text;
+};
+
+struct InputOptions
+{
+	char separator = 0; //separates items like spaces and line breaks, e.g. `;` or `,`
+	std::string comment_prefix; //e.g. `#` or `//`, no comment if it is empty
+	bool equal_required = false; //`name value` is not accepted, use `name = value` instead
+};
+
 class Table
 {
 	std::vector<Item> m_vect; unsigned int m_count = 0;
 	
 	int find_name(const std::string& name) const;
+	double effective_value(unsigned int index) const; //inversed if power_inversed is set
+	bool read(std::istream& ist, const InputOptions& opts, std::vector<InputError>& errors, bool additive);
 	
 public:
 	bool repetitive_picking = false;
 	bool power_inversed = false;
 	
+	Table() = default;
+	template <class InputIt> Table(InputIt first, InputIt last); //pairs of names and values
+	Table(std::initializer_list<std::pair<std::string, double>> list);
+	static Table uniform(const std::vector<std::string>& names); //each value is 1
+	static Table from_fn(const std::vector<std::string>& names, const std::function<double(const std::string&)>& weight_fn);
+	
 	unsigned int count() const;
 	bool is_empty() const;
 	
 	Item& operator[](unsigned int index);
-	double item_value(const std::string& name) const;
+	const Item& operator[](unsigned int index) const;
+	std::vector<Item>::iterator begin(); //items in the table, for range-based for loops
+	std::vector<Item>::iterator end();
+	std::vector<Item>::const_iterator begin() const;
+	std::vector<Item>::const_iterator end() const;
+	[[nodiscard]] bool contains(const std::string& name) const;
+	[[nodiscard]] double item_value(const std::string& name) const;
 	void item(Item& item);
 	
+	[[nodiscard]] bool check() const;
+	[[nodiscard]] bool check(std::string& name, std::string& reason) const; //gets the first invalid item and the reason
+	
+	// cumulative values (inversed if power_inversed is set) from 0, in which item i has the cell grid[i] ~ grid[i+1].
+	// possible items are those of nonzero cells, an item too small for the sum (like 1 beside 1e20) is impossible.
+	void grid(std::vector<double>& result) const;
+	[[nodiscard]] static unsigned int count_possible(const std::vector<double>& grid);
+	[[nodiscard]] unsigned int count_possible() const;
+	
+	// checks the table, and checks if the amount doesn't exceed possible items in non-repetitive mode.
+	[[nodiscard]] bool validate_amount(unsigned int amount) const;
+	[[nodiscard]] bool validate_amount(unsigned int amount, std::string& reason) const;
+	
+	[[nodiscard]] double total_value() const; //sum of valid values, inversed if power_inversed is set
+	[[nodiscard]] double min_value() const; //of possible items, inversed if power_inversed is set; 0 if there's no possible item
+	[[nodiscard]] double max_value() const;
+	[[nodiscard]] double value_ratio() const; //max / min, a large ratio may make the smallest item unreachable for a picker
+	[[nodiscard]] bool is_integral() const; //values (inversed if power_inversed is set) are integers with a sum below 2^53
+	void probabilities(Table& result) const; //probabilities of possible items when picking one item
+	[[nodiscard]] double entropy() const; //Shannon entropy (bits) of the probabilities
+	[[nodiscard]] double prob_before(const std::string& a, const std::string& b) const; //a is picked before b without repetition
+	
+	[[nodiscard]] unsigned int items_for_mass(double target) const; //amount of the most probable items covering the probability
+	[[nodiscard]] bool is_fair() const; //all values are equal
+	[[nodiscard]] bool is_fair(double eps) const; //values are equal within the relative tolerance
+	[[nodiscard]] bool passes_fairness(double max_deviation) const; //each probability is close to 1/n
+	void max_entropy_subset(unsigned int k, Table& result) const; //k items of the most balanced probabilities
+	
+	template <class InputIt> void extend(InputIt first, InputIt last); //the latter value of a name is kept
+	
 	void clear();
+	void sort(); //sorts items by names
+	void merge(const Table& other); //adds values of items in the other table, power_inversed must be the same
+	void subtract(const Table& other); //items of negative results are kept as impossible items of value 0
+	void retain(const std::function<bool(const std::string&, double)>& pred); //removes items not satisfying pred
+	void refresh(const std::function<double(const std::string&)>& weight_fn); //recomputes values, a picker uses them in the next pick; throws and keeps the table on an invalid value
+	void prune_below(double min_value);
+	void top_k(unsigned int k); //keeps k most probable items, items of equal values are ordered by names
 	void remove_impossible();
-	void scale(float scaler);
+	void scale(double scaler); //scaler must be positive
+	void normalize(); //makes the sum of probability values 1, inversed values are normalized if power_inversed is set
+	void normalize(double target); //the sum becomes target, values are not rounded even if target is an integer
 	void inverse();
 	bool input(std::istream& ist);
+	bool input(std::istream& ist, std::vector<InputError>& errors); //continues after invalid lines
+	bool input(std::istream& ist, const InputOptions& opts, std::vector<InputError>& errors);
+	bool input_additive(std::istream& ist); //values of an existing item are added up instead of being replaced
+	bool input_additive(std::istream& ist, std::vector<InputError>& errors);
 	bool output(std::ostream& ost) const;
+	bool input_json(std::istream& ist);
+	bool output_json(std::ostream& ost) const;
+	bool input_toml(std::istream& ist);
+	bool output_toml(std::ostream& ost) const;
 	
-	bool open(const std::string& path);
-	bool save(const std::string& path) const;
+	[[nodiscard]] bool open(const std::string& path);
+	[[nodiscard]] bool open(const std::string& path, std::vector<InputError>& errors); //continues after invalid lines
+	[[nodiscard]] bool save(const std::string& path) const;
 };
 
+template <class InputIt>
+Table::Table(InputIt first, InputIt last)
+{
+	this->extend(first, last);
+}
+
+inline Table::Table(std::initializer_list<std::pair<std::string, double>> list)
+{
+	this->extend(list.begin(), list.end());
+}
+
+inline Table Table::uniform(const std::vector<std::string>& names)
+{
+	Table tbl;
+	for (unsigned int i = 0; i < names.size(); i++) {
+		Item item(names[i], 1);
+		tbl.item(item);
+	}
+	return tbl;
+}
+
+inline Table Table::from_fn(const std::vector<std::string>& names, const std::function<double(const std::string&)>& weight_fn)
+{
+	Table tbl = Table::uniform(names);
+	tbl.refresh(weight_fn);
+	return tbl;
+}
+
 inline unsigned int Table::count() const
 {
 	return m_count;
@@ -97,6 +205,38 @@ inline Item& Table::operator[](unsigned int index)
 	return m_vect[index];
 }
 
+inline const Item& Table::operator[](unsigned int index) const
+{
+	if (index > m_count - 1)
+		throw std::invalid_argument("Table::operator[](): invalid index.");
+	return m_vect[index];
+}
+
+inline std::vector<Item>::iterator Table::begin()
+{
+	return m_vect.begin();
+}
+
+inline std::vector<Item>::iterator Table::end()
+{
+	return m_vect.begin() + m_count;
+}
+
+inline std::vector<Item>::const_iterator Table::begin() const
+{
+	return m_vect.begin();
+}
+
+inline std::vector<Item>::const_iterator Table::end() const
+{
+	return m_vect.begin() + m_count;
+}
+
+inline bool Table::contains(const std::string& name) const
+{
+	return this->find_name(name) >= 0;
+}
+
 inline double Table::item_value(const std::string& name) const
 {
 	int i = this->find_name(name);
@@ -113,11 +253,85 @@ inline void Table::item(Item& item)
 		m_vect[i].value(item.value());
 }
 
+inline double Table::effective_value(unsigned int index) const
+{
+	double val = m_vect[index].value();
+	if (this->power_inversed && val > 0) return 1.0 / val;
+	return val;
+}
+
+inline bool Table::check() const
+{
+	std::string name, reason;
+	return this->check(name, reason);
+}
+
+inline bool Table::validate_amount(unsigned int amount) const
+{
+	std::string reason;
+	return this->validate_amount(amount, reason);
+}
+
+inline bool Table::is_fair() const
+{
+	return this->is_fair(0);
+}
+
+template <class InputIt>
+void Table::extend(InputIt first, InputIt last)
+{
+	for (; first != last; ++first) {
+		Item item(first->first, first->second);
+		this->item(item);
+	}
+}
+
 inline void Table::clear()
 {
 	m_vect.clear(); m_count = 0;
 }
 
+inline bool Table::input(std::istream& ist)
+{
+	std::vector<InputError> errors;
+	return this->input(ist, errors);
+}
+
+inline bool Table::input(std::istream& ist, std::vector<InputError>& errors)
+{
+	return this->read(ist, InputOptions(), errors, false);
+}
+
+inline bool Table::input(std::istream& ist, const InputOptions& opts, std::vector<InputError>& errors)
+{
+	return this->read(ist, opts, errors, false);
+}
+
+inline bool Table::input_additive(std::istream& ist)
+{
+	std::vector<InputError> errors;
+	return this->read(ist, InputOptions(), errors, true);
+}
+
+inline bool Table::input_additive(std::istream& ist, std::vector<InputError>& errors)
+{
+	return this->read(ist, InputOptions(), errors, true);
+}
+
+// chi-square statistic of occurence counts (as returned by Picker::test()) against probabilities
+// (as returned by Picker::calculate()), n is the amount of groups, or picked items in repetitive mode.
+double chi_square(const Table& observed, const Table& expected, unsigned int n);
+
+// divergences between two tables of probabilities or frequencies, which are normalized before comparison;
+// an item missing in a table is regarded as an item of value 0.
+double kl_divergence(const Table& p, const Table& q); //in nats, infinity if q is 0 where p is not
+double total_variation(const Table& p, const Table& q);
+
+inline std::ostream& operator<<(std::ostream& ost, const Table& table)
+{
+	table.output(ost); return ost;
+}
+
 }
 
 #endif
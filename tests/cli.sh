@@ -0,0 +1,35 @@
+#!/bin/sh
+# tests of the command line program: sh tests/cli.sh ./random-picker
+bin=$(cd "$(dirname "$1")" && pwd)/$(basename "$1")
+dir=$(mktemp -d)
+trap 'rm -rf "$dir"' EXIT
+cd "$dir" || exit 1
+failures=0
+
+check() { #description, expected output, command...
+	desc=$1; expected=$2; shift 2
+	output=$("$@" 2>&1)
+	if [ "$output" != "$expected" ]; then
+		echo "check failed: $desc: got \"$output\""
+		failures=$((failures + 1))
+	fi
+}
+
+printf 'a 1\nb 0\n' > 1-5.txt
+check "a file named like a range" "a " "$bin" 1-5.txt 1
+check "a range after the file" "Absolute values (%) for amounts 1 to 1:" sh -c "\"$bin\" -s 1-5.txt 1-1 | sed -n 4p"
+check "an amount exceeding possible items" "Invalid amount 2: amount 2 exceeds 1 possible items." "$bin" 1-5.txt 2
+
+printf 'repetitive_picking\na 1\nb 3\n' > repetitive.txt
+"$bin" -t repetitive.txt 2 -j -o result.txt > /dev/null
+check "the mode saved by -t -j -o" "repetitive_picking" head -n 1 result.txt
+
+printf 'a 1\nb 2\n' > thirds.txt
+"$bin" -s thirds.txt 1 -o result.txt > /dev/null
+check "probabilities saved by -o" "a		0.33333333333333331" head -n 1 result.txt
+
+if [ $failures -gt 0 ]; then
+	echo "$failures check(s) failed."
+	exit 1
+fi
+echo "All tests passed."
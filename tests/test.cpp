@@ -0,0 +1,695 @@
+// by wuwbobo2021 <https://github.com/wuwbobo2021>, <wuwbobo@outlook.com>
+// If you have found bugs in this program, please pull an issue, or contact me.
+
+#include "random_picker.h"
+
+#include <algorithm>
+#include <cmath>
+#include <iostream>
+#include <limits>
+#include <map>
+#include <set>
+#include <sstream>
+#include <string>
+#include <vector>
+
+using namespace std;
+using namespace RandomPicker;
+
+unsigned int failures = 0;
+
+#define CHECK(cond) \
+	if (! (cond)) {cerr << __FILE__ << ':' << __LINE__ << ": check failed: " #cond "\n"; failures++;}
+
+void test_dot_tree()
+{
+	Table tbl{{"a", 1}, {"b", 2}, {"c", 3}};
+	Picker picker(tbl);
+	ostringstream sst;
+	CHECK(picker.output_dot_tree(2, sst));
+	
+	// leaves are nodes without outgoing edges: 3 * 2 groups of 2 items.
+	set<string> nodes, parents; string line;
+	istringstream ist(sst.str());
+	while (getline(ist, line)) {
+		istringstream words(line); string first, second;
+		words >> first >> second;
+		if (first.length() < 2 || first[0] != 'n') continue;
+		if (second == "->") parents.insert(first);
+		else nodes.insert(first);
+	}
+	CHECK(nodes.size() == 10);
+	CHECK(nodes.size() - parents.size() == 6);
+}
+
+void test_boundary_epsilon()
+{
+	// boundaries of b are closer than epsilon, a value inside b goes to a, the lowest index.
+	Table tbl{{"a", 1}, {"b", 1e-12}, {"c", 1}};
+	Picker picker(tbl);
+	unsigned long long in_b = (1ull << 63) + 1844674; //about 1 + 7e-13
+	unsigned long long in_c = (1ull << 63) + (1ull << 62); //about 1.5
+	
+	CHECK(picker.index_of(in_b) == 1);
+	CHECK(picker.boundary_epsilon(1e-9));
+	for (unsigned int i = 0; i < 3; i++)
+		CHECK(picker.index_of(in_b) == 0);
+	CHECK(picker.index_of(in_c) == 2);
+	CHECK(! picker.boundary_epsilon(-1));
+}
+
+void test_input()
+{
+	// a value may be on the line after its name, as in the original format.
+	Table tbl; vector<InputError> errors;
+	istringstream ist("x\n5\ny = 2\nz abc\nw\n");
+	CHECK(! tbl.input(ist, errors));
+	CHECK(tbl.item_value("x") == 5 && tbl.item_value("y") == 2);
+	CHECK(errors.size() == 2);
+	if (errors.size() == 2) {
+		CHECK(errors[0].line == 4 && errors[0].text == "z abc");
+		CHECK(errors[1].line == 5 && errors[1].text == "w");
+	}
+}
+
+void test_seeded()
+{
+	Table tbl{{"a", 1}, {"b", 2}, {"c", 3}, {"d", 4}};
+	tbl.repetitive_picking = true;
+	Picker picker_1(tbl, 42), picker_2(tbl, 42);
+	vector<string> result_1, result_2, result_3;
+	for (unsigned int i = 0; i < 10; i++) {
+		picker_1.pick(20, result_1); picker_2.pick(20, result_2);
+		CHECK(result_1 == result_2);
+	}
+	pick_seeded(tbl, 20, 7, result_1); pick_seeded(tbl, 20, 7, result_3);
+	CHECK(result_1 == result_3);
+}
+
+void test_sample_and_remove()
+{
+	// the small item remains after the large one is removed, it must be drawn at once.
+	Table deck{{"a", 1e9}, {"b", 1}, {"c", 0}};
+	Picker picker(deck);
+	set<string> drawn; double remaining = 1;
+	for (unsigned int i = 0; i < 2; i++)
+		drawn.insert(picker.sample_and_remove(remaining));
+	CHECK(drawn.size() == 2 && drawn.count("c") == 0);
+	CHECK(remaining == 0);
+	
+	bool thrown = false;
+	try {picker.sample_and_remove();} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+	
+	// items of a group drawn one by one are distinct.
+	Table tbl{{"a", 1}, {"b", 2}, {"c", 3}, {"d", 4}, {"e", 5}};
+	Picker picker_group(tbl);
+	for (unsigned int t = 0; t < 100; t++) {
+		picker_group.begin_group();
+		for (unsigned int i = 0; i < 4; i++) picker_group.draw_next();
+		vector<unsigned int> group; picker_group.finish_group(group);
+		CHECK(set<unsigned int>(group.begin(), group.end()).size() == 4);
+	}
+	
+	// an item added during a group isn't drawn from the old grid.
+	picker_group.begin_group();
+	picker_group.draw_next();
+	Item item("f", 6); tbl.item(item);
+	thrown = false;
+	try {picker_group.draw_next();} catch (const std::logic_error&) {thrown = true;}
+	CHECK(thrown);
+	vector<unsigned int> group; picker_group.finish_group(group);
+}
+
+void test_refresh()
+{
+	bool thrown = false;
+	try {
+		Table::from_fn({"x", "y"}, [](const string& name) {return name == "x" ? -5.0 : 1.0;});
+	} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+	
+	Table tbl{{"x", 2}, {"y", 3}};
+	thrown = false;
+	try {
+		tbl.refresh([](const string& name) {return name == "x" ? 4.0 : NAN;});
+	} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+	CHECK(tbl[0].value() == 2 && tbl[1].value() == 3);
+	
+	tbl.refresh([](const string& name) {return name == "x" ? 4.0 : 0.0;});
+	CHECK(tbl[0].value() == 4 && tbl[1].value() == 0);
+}
+
+void test_amount_of_possible_items()
+{
+	// c is impossible, so at most 2 items can be picked without repetition.
+	Table tbl{{"a", 1}, {"b", 1}, {"c", 0}};
+	Picker picker(tbl);
+	vector<unsigned int> result;
+	
+	picker.pick(2, result);
+	CHECK(result.size() == 2 && result[0] != result[1]);
+	
+	bool thrown = false;
+	try {picker.pick(3, result);} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+	thrown = false;
+	try {picker.pick_groups(2, 3, result);} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+	thrown = false;
+	try {picker.pick_each(3, [](unsigned int) {});} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+	thrown = false;
+	try {picker.pick_chunks(3, [](const vector<unsigned int>&) {return false;});} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+	thrown = false;
+	try {picker.pick_min_distinct(3, 1, 10, result);} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+	CHECK(picker.pick_min_distinct(2, 2, 10, result) && result.size() == 2);
+	
+	// calculations use the same amount of possible items.
+	Table cal;
+	thrown = false;
+	try {picker.calculate(3, cal);} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+	thrown = false;
+	try {(void)picker.expected_draws(3);} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+	picker.calculate(2, cal);
+	CHECK(cal.count() == 2 && cal[0].value() == 1 && cal[1].value() == 1);
+	
+	// a failed test() doesn't leave the picker using the old grid.
+	thrown = false;
+	try {picker.test(10, 3, cal);} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+	Item item("d", 100); tbl.item(item);
+	unsigned int cnt_d = 0;
+	for (unsigned int i = 0; i < 1000; i++) {
+		picker.pick(1, result);
+		if (result[0] == 3) cnt_d++;
+	}
+	CHECK(cnt_d > 900);
+	picker.pick(3, result);
+	CHECK(result.size() == 3);
+}
+
+void test_index_of()
+{
+	// the grid is {0, 1, 1, 2, 4, 4}, a value at a boundary belongs to the next possible cell.
+	Table tbl{{"a", 1}, {"b", 0}, {"c", 1}, {"d", 2}, {"e", 0}};
+	Picker picker(tbl);
+	
+	CHECK(picker.index_of(0) == 0);
+	CHECK(picker.index_of((1ull << 62) - (1ull << 10)) == 0); //1 - 2^-52
+	CHECK(picker.index_of(1ull << 62) == 2); //1, b is impossible
+	CHECK(picker.index_of((1ull << 63) - (1ull << 11)) == 2);
+	CHECK(picker.index_of(1ull << 63) == 3); //2
+	CHECK(picker.index_of(~0ull) == 3); //rounded to the total, e is impossible
+	
+	Table tbl_zero{{"a", 0}};
+	Picker picker_zero(tbl_zero);
+	bool thrown = false;
+	try {(void)picker_zero.index_of(0);} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+}
+
+void test_reset()
+{
+	Table tbl{{"a", 1}, {"b", 2}, {"c", 3}, {"d", 4}, {"e", 5}};
+	Picker picker(tbl);
+	picker.begin_group();
+	picker.draw_next(); picker.draw_next();
+	
+	tbl.top_k(2); //d and e
+	CHECK(picker.reset() == 2);
+	
+	vector<unsigned int> group;
+	picker.finish_group(group);
+	CHECK(group.empty()); //the group was dropped
+	
+	for (unsigned int t = 0; t < 20; t++) {
+		picker.begin_group();
+		unsigned int n1 = picker.draw_next(), n2 = picker.draw_next();
+		CHECK(n1 < 2 && n2 < 2 && n1 != n2);
+		bool thrown = false;
+		try {picker.draw_next();} catch (const std::invalid_argument&) {thrown = true;}
+		CHECK(thrown);
+		picker.finish_group(group);
+	}
+	
+	vector<unsigned int> result;
+	picker.pick(2, result);
+	CHECK(result.size() == 2 && result[0] < 2 && result[1] < 2);
+	bool thrown = false;
+	try {picker.pick(3, result);} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+	
+	// the table grows during a group.
+	picker.begin_group();
+	picker.draw_next();
+	Item item_f("f", 6), item_g("g", 0); tbl.item(item_f); tbl.item(item_g);
+	CHECK(picker.reset() == 3);
+	picker.begin_group();
+	for (unsigned int i = 0; i < 3; i++) picker.draw_next();
+	picker.finish_group(group);
+	CHECK(set<unsigned int>(group.begin(), group.end()) == set<unsigned int>({0, 1, 2}));
+}
+
+void test_validate_amount()
+{
+	Table tbl_all{{"a", 1}, {"b", 2}};
+	CHECK(tbl_all.validate_amount(tbl_all.count()) && ! tbl_all.validate_amount(tbl_all.count() + 1));
+	
+	Table tbl{{"a", 1}, {"b", 2}, {"c", 0}};
+	CHECK(tbl.validate_amount(0) && tbl.validate_amount(2));
+	CHECK(! tbl.validate_amount(3)); //c is impossible
+	tbl.repetitive_picking = true;
+	CHECK(tbl.validate_amount(3));
+	
+	// b is too small for the sum, so that a picker can't pick it.
+	Table tbl_big{{"a", 1e20}, {"b", 1}};
+	CHECK(tbl_big.count_possible() == 1);
+	CHECK(tbl_big.validate_amount(1) && ! tbl_big.validate_amount(2));
+	
+	string reason;
+	Table tbl_zero{{"a", 0}};
+	CHECK(! tbl_zero.validate_amount(0, reason) && reason == "no possible item");
+}
+
+void test_zero_amount()
+{
+	Table tbl{{"a", 1}, {"b", 3}, {"c", 0}};
+	Picker picker(tbl);
+	vector<unsigned int> result{1, 2};
+	picker.pick(0, result);
+	CHECK(result.empty());
+	
+	Table stat, cal, approx;
+	picker.test(100, 0, stat);
+	picker.calculate(0, cal);
+	picker.calculate_approx(0, 100, approx);
+	CHECK(stat.count() == 2 && cal.count() == 2 && approx.count() == 2);
+	for (unsigned int i = 0; i < 2; i++)
+		CHECK(stat[i].value() == 0 && cal[i].value() == 0 && approx[i].value() == 0);
+	
+	// results are replaced even if there is nothing to calculate.
+	Table tbl_empty, tbl_zero{{"a", 0}, {"b", 0}};
+	Picker picker_empty(tbl_empty), picker_zero(tbl_zero);
+	picker_empty.calculate(1, cal);
+	CHECK(cal.is_empty());
+	picker_empty.calculate_approx(1, 100, approx);
+	CHECK(approx.is_empty());
+	
+	picker.calculate(1, cal); picker.calculate(1, {"a"}, approx);
+	picker_zero.calculate(1, cal);
+	CHECK(cal.is_empty());
+	picker_zero.calculate(1, {"a"}, approx);
+	CHECK(approx.is_empty());
+	picker.calculate_approx(1, 100, approx);
+	picker_zero.calculate_approx(1, 100, approx);
+	CHECK(approx.is_empty());
+}
+
+void test_remaining_mass()
+{
+	Table deck{{"a", 1}, {"b", 2}, {"c", 0}, {"d", 3}, {"e", 0.1}};
+	Picker picker(deck);
+	for (unsigned int t = 0; t < 20; t++) {
+		picker.begin_group();
+		double last = 1, remaining;
+		for (unsigned int i = 0; i < 4; i++) {
+			picker.sample_and_remove(remaining);
+			CHECK(remaining < last && remaining >= 0);
+			last = remaining;
+		}
+		CHECK(remaining == 0);
+	}
+	
+	// the remaining probability is of the items left in the deck.
+	Table deck_fixed{{"a", 1}, {"b", 3}};
+	Picker picker_fixed(deck_fixed);
+	double remaining;
+	string name = picker_fixed.sample_and_remove(remaining);
+	CHECK(remaining == (name == "a"? 0.75 : 0.25));
+}
+
+void test_weighted_index()
+{
+	WeightedIndex index({1, 0, 1, 2, 0});
+	CHECK(index.count() == 5 && index.total() == 4);
+	CHECK(index.find(0) == 0 && index.find(0.5) == 0);
+	CHECK(index.find(1) == 2); //1 is impossible
+	CHECK(index.find(2) == 3 && index.find(4) == 3); //4 is impossible
+	
+	// a picker finds the same items in its grid.
+	Table tbl{{"a", 1}, {"b", 0}, {"c", 1}, {"d", 2}, {"e", 0}};
+	Picker picker(tbl);
+	for (unsigned long long raw = 0; raw < ~0ull - (1ull << 58); raw += 1ull << 58)
+		CHECK(picker.index_of(raw) == index.find(4 * ((double)raw / 18446744073709551616.0)));
+	
+	mt19937 engine(1);
+	for (unsigned int i = 0; i < 1000; i++) {
+		unsigned int n = index(engine);
+		CHECK(n != 1 && n != 4);
+	}
+}
+
+void test_copy()
+{
+	Table tbl{{"a", 1}, {"b", 2}, {"c", 3}};
+	Picker picker(tbl, 7);
+	picker.boundary_epsilon(1e-9);
+	vector<unsigned int> result, result_copy;
+	picker.pick(2, result);
+	
+	// the copy has the settings, and draws the grid of the current table.
+	Picker picker_copy(picker, 7), picker_seeded(tbl, 7);
+	picker_seeded.boundary_epsilon(1e-9);
+	CHECK(picker_copy.boundary_epsilon() == 1e-9);
+	picker_copy.pick(2, result_copy); picker_seeded.pick(2, result);
+	CHECK(result_copy == result);
+	
+	tbl[0].value(0); tbl[1].value(0);
+	for (unsigned int i = 0; i < 10; i++)
+		CHECK(Picker(picker, i).pick_index() == 2);
+}
+
+void test_seeded_sequence()
+{
+	// the sequence of std::mt19937 is specified by the standard (its 10000th value is 4123659995),
+	// so these results must be the same on every platform unless the grid or random_value() is changed.
+	mt19937 engine;
+	engine.discard(9999);
+	CHECK(engine() == 4123659995u);
+	
+	Table tbl{{"a", 1}, {"b", 2}, {"c", 3}, {"d", 4}};
+	Picker picker(tbl, 2021);
+	vector<unsigned int> seq;
+	for (unsigned int i = 0; i < 12; i++)
+		seq.push_back(picker.pick_index());
+	CHECK(seq == vector<unsigned int>({3, 3, 3, 2, 1, 3, 2, 3, 3, 2, 1, 3}));
+	
+	vector<string> result;
+	pick_seeded(tbl, 3, 2021, result);
+	CHECK(result == vector<string>({"d", "c", "b"}));
+}
+
+void test_non_finite_values()
+{
+	const double values[] = {NAN, INFINITY, -INFINITY};
+	for (double val : values) {
+		bool thrown = false;
+		try {Table tbl{{"a", 1}, {"b", val}};} catch (const std::invalid_argument&) {thrown = true;}
+		CHECK(thrown);
+		
+		Item item("a", 1);
+		CHECK(! item.value(val) && item.value() == 1);
+	}
+	
+	Table tbl{{"a", 1}, {"b", 2}};
+	istringstream sst("c nan\nd inf\n");
+	vector<InputError> errors;
+	CHECK(! tbl.input_additive(sst, errors) && errors.size() == 2);
+	CHECK(tbl.count() == 2 && tbl.check());
+}
+
+void test_items_for_mass()
+{
+	Table tbl{{"a", 70}, {"b", 15}, {"c", 5}, {"d", 5}, {"e", 5}, {"f", 0}};
+	CHECK(tbl.items_for_mass(0.8) == 2);
+	CHECK(tbl.items_for_mass(0.7) == 1); //exactly reached
+	CHECK(tbl.items_for_mass(0) == 0);
+	CHECK(tbl.items_for_mass(1) == 5);
+	
+	bool thrown = false;
+	try {(void)tbl.items_for_mass(1.5);} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+}
+
+void test_toml()
+{
+	Table tbl{{"a", 1.0 / 3}, {"b", 2}, {"c", 0}};
+	tbl.repetitive_picking = true; tbl.power_inversed = true;
+	stringstream sst;
+	CHECK(tbl.output_toml(sst));
+	
+	Table tbl_read;
+	CHECK(tbl_read.input_toml(sst));
+	CHECK(tbl_read.repetitive_picking && tbl_read.power_inversed);
+	CHECK(tbl_read.count() == 3);
+	for (unsigned int i = 0; i < tbl.count(); i++)
+		CHECK(tbl_read[i].name() == tbl[i].name() && tbl_read[i].value() == tbl[i].value());
+	
+	const char* malformed[] = {"[items]\na 1\n", "[items]\na = -1\n", "[other]\n", "[random-picker]\nrepetitive_picking = yes\n"};
+	for (const char* str : malformed) {
+		istringstream sst_bad(str); Table tbl_bad;
+		CHECK(! tbl_bad.input_toml(sst_bad));
+	}
+}
+
+void test_shuffle()
+{
+	Table tbl{{"a", 1}, {"b", 1}, {"c", 8}, {"d", 0}};
+	Picker picker(tbl, 1);
+	unsigned int cnt_c_first = 0;
+	for (unsigned int t = 0; t < 2000; t++) {
+		vector<string> order; picker.shuffle(order);
+		CHECK(set<string>(order.begin(), order.end()) == set<string>({"a", "b", "c"}) && order.size() == 3);
+		if (order[0] == "c") cnt_c_first++;
+	}
+	CHECK(cnt_c_first > 1500 && cnt_c_first < 1700); //0.8 of the results
+}
+
+void test_inclusion_bounds()
+{
+	Table tbl{{"a", 1}, {"b", 3}, {"c", 0}};
+	Picker picker(tbl);
+	double lower, upper;
+	CHECK(picker.inclusion_bounds("a", lower, upper));
+	CHECK(lower == 0.25 && upper == 1);
+	Table cal; picker.calculate(1, cal);
+	CHECK(lower == cal.item_value("a"));
+	
+	CHECK(picker.inclusion_bounds("c", lower, upper) && lower == 0 && upper == 0);
+	CHECK(! picker.inclusion_bounds("x", lower, upper));
+	
+	tbl.repetitive_picking = true; //picking 3 times at most
+	CHECK(picker.inclusion_bounds("a", lower, upper));
+	CHECK(lower == 0.25 && std::abs(upper - (1 - 0.75 * 0.75 * 0.75)) < 1e-12);
+}
+
+void test_replay()
+{
+	Table tbl{{"a", 1}, {"b", 2}, {"c", 3}, {"d", 4}};
+	Picker picker(tbl, 5), picker_names(tbl, 5);
+	vector<unsigned int> indexes; vector<string> names, replayed;
+	picker.pick(3, indexes); picker_names.pick(3, names);
+	picker.replay(indexes, replayed);
+	CHECK(replayed == names);
+	
+	bool thrown = false;
+	try {picker.replay({0, 4}, replayed);} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+	CHECK(replayed == names); //kept on failure
+}
+
+void test_passes_fairness()
+{
+	Table tbl{{"a", 24}, {"b", 25}, {"c", 25}, {"d", 26}}; //deviations are 0.01 at most
+	CHECK(tbl.passes_fairness(0.05));
+	CHECK(! tbl.passes_fairness(0.005));
+	
+	Table tbl_zero{{"a", 0}};
+	CHECK(! tbl_zero.passes_fairness(1)); //invalid table
+}
+
+void test_on_pick()
+{
+	Table tbl{{"a", 1}, {"b", 2}, {"c", 30}, {"d", 4}};
+	Picker picker(tbl, 9), picker_plain(tbl, 9);
+	vector<unsigned int> drawn;
+	picker.on_pick([&](unsigned int n) {drawn.push_back(n);});
+	
+	// every drawn index is given once, including rejected ones; the first occurences are the result.
+	for (unsigned int t = 0; t < 50; t++) {
+		vector<unsigned int> result, result_plain, firsts;
+		drawn.clear();
+		picker.pick(3, result); picker_plain.pick(3, result_plain);
+		CHECK(result == result_plain);
+		for (unsigned int n : drawn)
+			if (find(firsts.begin(), firsts.end(), n) == firsts.end()) firsts.push_back(n);
+		CHECK(drawn.size() >= 3 && firsts == result);
+	}
+	
+	drawn.clear();
+	unsigned int n = picker.pick_index();
+	CHECK(drawn.size() == 1 && drawn[0] == n);
+}
+
+void test_max_entropy_subset()
+{
+	Table tbl{{"a", 100}, {"b", 1}, {"c", 1}, {"d", 1}}, result;
+	tbl.max_entropy_subset(2, result);
+	CHECK(result.count() == 2 && ! result.contains("a")); //two of the equal items
+	CHECK(std::abs(result.entropy() - 1) < 1e-12);
+	
+	tbl.max_entropy_subset(4, result);
+	CHECK(result.count() == 4);
+	
+	bool thrown = false;
+	try {tbl.max_entropy_subset(5, result);} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown);
+}
+
+void test_entropy()
+{
+	for (unsigned int n = 1; n <= 16; n *= 2) {
+		vector<string> names;
+		for (unsigned int i = 0; i < n; i++) names.push_back("i" + to_string(i));
+		CHECK(Table::uniform(names).entropy() == std::log2((double)n));
+	}
+	
+	Table tbl{{"a", 1}, {"b", 3}, {"c", 0}};
+	CHECK(std::abs(tbl.entropy() - (0.25 * 2 + 0.75 * std::log2(4.0 / 3))) < 1e-12);
+	tbl.power_inversed = true; //the same as {a: 3, b: 1}
+	CHECK(std::abs(tbl.entropy() - (0.25 * 2 + 0.75 * std::log2(4.0 / 3))) < 1e-12);
+}
+
+void test_unreachable_items()
+{
+	Table tbl{{"a", 1}, {"b", 1e-40}, {"c", 0}};
+	Picker picker(tbl);
+	vector<string> result;
+	picker.unreachable_items(result);
+	CHECK(result == vector<string>({"b"})); //c is impossible rather than unreachable
+	
+	Table tbl_small{{"a", 1}, {"b", 1e-3}};
+	Picker picker_small(tbl_small);
+	picker_small.unreachable_items(result);
+	CHECK(result.empty());
+}
+
+void test_scale()
+{
+	Table tbl{{"a", 1}, {"b", 3}, {"c", 0}}, prob, prob_scaled;
+	tbl.probabilities(prob);
+	tbl.scale(0.125);
+	CHECK(tbl[0].value() == 0.125 && tbl[1].value() == 0.375 && tbl[2].value() == 0);
+	tbl.probabilities(prob_scaled);
+	for (unsigned int i = 0; i < prob.count(); i++)
+		CHECK(prob_scaled.item_value(prob[i].name()) == prob[i].value());
+	
+	const double scalers[] = {0, -1, NAN, INFINITY};
+	for (double scaler : scalers) {
+		bool thrown = false;
+		try {tbl.scale(scaler);} catch (const std::invalid_argument&) {thrown = true;}
+		CHECK(thrown && tbl[1].value() == 0.375);
+	}
+	
+	Table tbl_large{{"a", 1}, {"b", 1e300}};
+	bool thrown = false;
+	try {tbl_large.scale(1e10);} catch (const std::invalid_argument&) {thrown = true;}
+	CHECK(thrown && tbl_large[0].value() == 1); //nothing is changed on overflow
+}
+
+void test_value_overflow()
+{
+	const double max = numeric_limits<double>::max();
+	string name, reason;
+	Table tbl{{"a", max}, {"b", max}};
+	CHECK(! tbl.check(name, reason) && name == "a" && reason == "value too large");
+	CHECK(! tbl.validate_amount(1));
+	
+	Table tbl_half{{"a", max / 2}, {"b", max / 2}};
+	CHECK(tbl_half.check() && std::isfinite(tbl_half.total_value()));
+	
+	Table tbl_inversed{{"a", 1}, {"b", 1e-320}}; //1 / 1e-320 overflows
+	tbl_inversed.power_inversed = true;
+	CHECK(! tbl_inversed.check(name, reason) && name == "b");
+}
+
+void test_large_repetitive_amount()
+{
+	// amounts above 2^31 - 1 must not wrap in exponents.
+	Table tbl{{"a", 1}, {"b", 2}, {"c", 1e-6}};
+	tbl.repetitive_picking = true;
+	Picker picker(tbl);
+	const unsigned int amount = 3000000000u;
+	
+	for (unsigned int i = 0; i < tbl.count(); i++) {
+		double p = picker.prob_joint({tbl[i].name()}, amount);
+		CHECK(p > 0.999 && p <= 1);
+		double lower, upper;
+		CHECK(picker.inclusion_bounds(tbl[i].name(), lower, upper) && upper <= 1);
+	}
+	CHECK(std::abs(picker.covariance("a", "c", amount)) < 1e-9);
+	CHECK(picker.expected_draws(amount) == amount);
+}
+
+void test_stratified()
+{
+	Table categories{{"fruit", 1}, {"veg", 3}};
+	map<string, Table> items{{"fruit", Table{{"apple", 1}, {"pear", 1}}}, {"veg", Table{{"kale", 2}, {"leek", 6}}}};
+	StratifiedPicker picker(categories, items, 3);
+	
+	// the marginal probabilities are the same as the flat table of products.
+	Table flat{{"fruit__apple", 0.125}, {"fruit__pear", 0.125}, {"veg__kale", 0.1875}, {"veg__leek", 0.5625}};
+	Table result, prob_flat;
+	picker.flatten(result); flat.probabilities(prob_flat);
+	CHECK(result.count() == 4);
+	for (unsigned int i = 0; i < prob_flat.count(); i++)
+		CHECK(std::abs(result.item_value(prob_flat[i].name()) - prob_flat[i].value()) < 1e-12);
+	
+	const unsigned int times = 40000;
+	vector<pair<string, string>> picked;
+	picker.pick(times, picked);
+	map<string, unsigned int> stat;
+	for (unsigned int i = 0; i < picked.size(); i++) {
+		CHECK(items[picked[i].first].contains(picked[i].second));
+		stat[picked[i].first + "__" + picked[i].second]++;
+	}
+	for (unsigned int i = 0; i < prob_flat.count(); i++)
+		CHECK(std::abs((double)stat[prob_flat[i].name()] / times - prob_flat[i].value()) < 0.01);
+}
+
+int main()
+{
+	test_dot_tree();
+	test_boundary_epsilon();
+	test_input();
+	test_seeded();
+	test_sample_and_remove();
+	test_refresh();
+	test_amount_of_possible_items();
+	test_index_of();
+	test_reset();
+	test_validate_amount();
+	test_zero_amount();
+	test_remaining_mass();
+	test_weighted_index();
+	test_copy();
+	test_seeded_sequence();
+	test_non_finite_values();
+	test_items_for_mass();
+	test_toml();
+	test_shuffle();
+	test_inclusion_bounds();
+	test_replay();
+	test_passes_fairness();
+	test_on_pick();
+	test_max_entropy_subset();
+	test_entropy();
+	test_unreachable_items();
+	test_scale();
+	test_value_overflow();
+	test_large_repetitive_amount();
+	test_stratified();
+	
+	if (failures > 0) {
+		cerr << failures << " check(s) failed.\n";
+		return 1;
+	}
+	cout << "All tests passed.\n";
+	return 0;
+}
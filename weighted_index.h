@@ -0,0 +1,92 @@
+// by wuwbobo2021 <https://github.com/wuwbobo2021>, <wuwbobo@outlook.com>
+// If you have found bugs in this program, please pull an issue, or contact me.
+
+#ifndef RAMDOM_PICKER_WEIGHTED_INDEX_H
+#define RAMDOM_PICKER_WEIGHTED_INDEX_H
+
+#include <algorithm>
+#include <cmath>
+#include <random>
+#include <stdexcept>
+#include <vector>
+
+namespace RandomPicker
+{
+
+// picks indexes of weights with probabilities proportional to them, for callers keeping items elsewhere.
+// it can be used with any engine of <random>. an index is found in O(log n) time.
+// Picker searches its own grid, which it keeps for boundary_epsilon() and integer_grid(), with find().
+class WeightedIndex
+{
+	std::vector<double> m_cdf; //cumulative weights
+	
+public:
+	WeightedIndex(const std::vector<double>& weights); //weights should be finite and non-negative, not all 0
+	
+	unsigned int count() const;
+	double total() const;
+	double weight(unsigned int index) const;
+	unsigned int find(double val) const; //index of the cell in which val (0 ~ total) is
+	
+	// the same for cumulative weights of count cells, val in [cdf[i-1], cdf[i]) gives i (cdf[-1] is 0);
+	// val of the total gives the last cell of nonzero width.
+	static unsigned int find(const double* cdf, unsigned int count, double val);
+	
+	template <class URBG> unsigned int operator()(URBG& engine) const;
+};
+
+inline WeightedIndex::WeightedIndex(const std::vector<double>& weights)
+{
+	double cur = 0;
+	for (unsigned int i = 0; i < weights.size(); i++) {
+		if (! (weights[i] >= 0) || ! std::isfinite(weights[i]))
+			throw std::invalid_argument("WeightedIndex::WeightedIndex(): invalid weight.");
+		cur += weights[i]; m_cdf.push_back(cur);
+	}
+	if (! (cur > 0) || ! std::isfinite(cur))
+		throw std::invalid_argument("WeightedIndex::WeightedIndex(): invalid sum of weights.");
+}
+
+inline unsigned int WeightedIndex::count() const
+{
+	return m_cdf.size();
+}
+
+inline double WeightedIndex::total() const
+{
+	return m_cdf.back();
+}
+
+inline double WeightedIndex::weight(unsigned int index) const
+{
+	return (index == 0)? m_cdf[0] : m_cdf[index] - m_cdf[index - 1];
+}
+
+inline unsigned int WeightedIndex::find(double val) const
+{
+	return WeightedIndex::find(m_cdf.data(), m_cdf.size(), val);
+}
+
+inline unsigned int WeightedIndex::find(const double* cdf, unsigned int count, double val)
+{
+	unsigned int i = std::upper_bound(cdf, cdf + count, val) - cdf;
+	if (i < count) return i;
+	
+	i = count - 1; //val is total, the last possible item is chosen
+	while (i > 0 && cdf[i] == cdf[i - 1]) i--;
+	return i;
+}
+
+template <class URBG>
+unsigned int WeightedIndex::operator()(URBG& engine) const
+{
+	double val;
+	do {
+		val = std::generate_canonical<double, 64>(engine) * m_cdf.back();
+	} while (val >= m_cdf.back()); //some implementations of generate_canonical may return 1.0
+	
+	return this->find(val);
+}
+
+}
+#endif